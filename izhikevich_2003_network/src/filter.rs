@@ -0,0 +1,67 @@
+use std::f64::consts::TAU;
+
+/// Band-pass biquad (RBJ cookbook, 0 dB peak gain).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BandPass {
+    b0: f64,
+    a1: f64,
+    a2: f64,
+    x1: f64,
+    x2: f64,
+    y1: f64,
+    y2: f64,
+}
+
+impl BandPass {
+    pub fn new(low_hz: f64, high_hz: f64, sample_hz: f64) -> Self {
+        let f0 = (low_hz * high_hz).sqrt();
+        let q = f0 / (high_hz - low_hz);
+        let w0 = TAU * f0 / sample_hz;
+        let alpha = w0.sin() / (2.0 * q);
+        let a0 = 1.0 + alpha;
+        Self {
+            b0: alpha / a0,
+            a1: -2.0 * w0.cos() / a0,
+            a2: (1.0 - alpha) / a0,
+            ..Self::default()
+        }
+    }
+
+    pub fn process(&mut self, x: f64) -> f64 {
+        let y = self.b0 * (x - self.x2) - self.a1 * self.y1 - self.a2 * self.y2;
+        self.x2 = self.x1;
+        self.x1 = x;
+        self.y2 = self.y1;
+        self.y1 = y;
+        y
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Steady-state output amplitude for a unit sine at `hz`.
+    fn gain(hz: f64) -> f64 {
+        let sample_hz = 1000.0;
+        let mut filter = BandPass::new(8.0, 12.0, sample_hz);
+        (0..4000)
+            .map(|k| filter.process((TAU * hz * k as f64 / sample_hz).sin()))
+            .skip(3000)
+            .fold(0.0, |peak: f64, y| peak.max(y.abs()))
+    }
+
+    #[test]
+    fn passes_the_centre_and_rejects_the_rest() {
+        assert!((gain(96f64.sqrt()) - 1.0).abs() < 0.01);
+        assert!(gain(1.0) < 0.1);
+        assert!(gain(100.0) < 0.1);
+    }
+
+    #[test]
+    fn blocks_dc() {
+        let mut filter = BandPass::new(8.0, 12.0, 1000.0);
+        let last = (0..5000).map(|_| filter.process(1.0)).last().unwrap();
+        assert!(last.abs() < 1e-6);
+    }
+}
@@ -0,0 +1,390 @@
+mod filter;
+
+use filter::BandPass;
+use izhikevich_2003_neuron_rust::integrator::Integrator;
+use izhikevich_2003_neuron_rust::model::Equations;
+use izhikevich_2003_neuron_rust::rng::Rng;
+use rtsyn_plugin::prelude::*;
+use serde_json::Value;
+
+// The network is updated in 1 ms steps, as in the paper's MATLAB code.
+const STEP_MS: f64 = 1.0;
+// The weights are a dense `n * n` matrix: 32 MB at the cap.
+const MAX_NEURONS: usize = 2_000;
+
+/// Random cortical network of Izhikevich (2003): `ne` excitatory and `ni`
+/// inhibitory neurons, all-to-all random weights and thalamic noise.
+#[derive(Debug)]
+struct Izhikevich2003Network {
+    ne: usize,
+    ni: usize,
+    seed: u64,
+    noise_e: f64,
+    noise_i: f64,
+    rate_tau_ms: f64,
+    i_ext: f64,
+    v: Vec<f64>,
+    u: Vec<f64>,
+    a: Vec<f64>,
+    b: Vec<f64>,
+    c: Vec<f64>,
+    d: Vec<f64>,
+    // Column-major weights: `weights[pre * n + post]`.
+    weights: Vec<f64>,
+    input: Vec<f64>,
+    fired: Vec<usize>,
+    rng: Rng,
+    // Noise generator as left by `build`, for restarts.
+    initial_rng: Rng,
+    pending_ms: f64,
+    spikes_this_tick: u32,
+    total_spikes: u64,
+    rate_hz: f64,
+    rate_e_hz: f64,
+    rate_i_hz: f64,
+    alpha: BandPass,
+    gamma: BandPass,
+    alpha_hz: f64,
+    gamma_hz: f64,
+    last_tick: Option<u64>,
+}
+
+impl Default for Izhikevich2003Network {
+    fn default() -> Self {
+        let mut network = Self {
+            ne: 800,
+            ni: 200,
+            seed: 1,
+            noise_e: 5.0,
+            noise_i: 2.0,
+            rate_tau_ms: 10.0,
+            i_ext: 0.0,
+            v: Vec::new(),
+            u: Vec::new(),
+            a: Vec::new(),
+            b: Vec::new(),
+            c: Vec::new(),
+            d: Vec::new(),
+            weights: Vec::new(),
+            input: Vec::new(),
+            fired: Vec::new(),
+            rng: Rng::new(1),
+            initial_rng: Rng::new(1),
+            pending_ms: 0.0,
+            spikes_this_tick: 0,
+            total_spikes: 0,
+            rate_hz: 0.0,
+            rate_e_hz: 0.0,
+            rate_i_hz: 0.0,
+            alpha: BandPass::default(),
+            gamma: BandPass::default(),
+            alpha_hz: 0.0,
+            gamma_hz: 0.0,
+            last_tick: None,
+        };
+        network.build();
+        network
+    }
+}
+
+impl PluginDescriptor for Izhikevich2003Network {
+    fn name() -> &'static str {
+        "Izhikevich 2003 Network"
+    }
+
+    fn kind() -> &'static str {
+        "izhikevich_2003_network"
+    }
+
+    fn plugin_type() -> PluginType {
+        PluginType::Computational
+    }
+
+    fn inputs() -> &'static [&'static str] {
+        &["i_syn"]
+    }
+
+    fn outputs() -> &'static [&'static str] {
+        &[
+            "Spikes this tick",
+            "Total spikes",
+            "Population rate (Hz)",
+            "Excitatory rate (Hz)",
+            "Inhibitory rate (Hz)",
+            "Mean membrane potential (mV)",
+            "Alpha rhythm (8-12 Hz)",
+            "Gamma rhythm (30-80 Hz)",
+        ]
+    }
+
+    fn internal_variables() -> &'static [&'static str] {
+        &["n", "mean_u"]
+    }
+
+    fn default_vars() -> Vec<(&'static str, Value)> {
+        vec![
+            ("seed", 1.into()),
+            ("ne", 800.into()),
+            ("ni", 200.into()),
+            ("noise_e", 5.0.into()),
+            ("noise_i", 2.0.into()),
+            ("rate_tau_ms", 10.0.into()),
+        ]
+    }
+
+    fn behavior() -> PluginBehavior {
+        PluginBehavior {
+            supports_start_stop: true,
+            supports_restart: true,
+            supports_apply: false,
+            extendable_inputs: ExtendableInputs::None,
+            loads_started: false,
+            external_window: false,
+            starts_expanded: true,
+            start_requires_connected_inputs: Vec::new(),
+            start_requires_connected_outputs: Vec::new(),
+        }
+    }
+}
+
+impl Izhikevich2003Network {
+    fn len(&self) -> usize {
+        self.ne + self.ni
+    }
+
+    /// Draws parameters, weights and initial state from `seed`, following
+    /// the heterogeneity rules of the paper.
+    fn build(&mut self) {
+        let (ne, n) = (self.ne, self.len());
+        let mut rng = Rng::new(self.seed);
+
+        self.a.clear();
+        self.b.clear();
+        self.c.clear();
+        self.d.clear();
+        for _ in 0..ne {
+            let re = rng.uniform();
+            self.a.push(0.02);
+            self.b.push(0.2);
+            self.c.push(-65.0 + 15.0 * re * re);
+            self.d.push(8.0 - 6.0 * re * re);
+        }
+        for _ in ne..n {
+            let ri = rng.uniform();
+            self.a.push(0.02 + 0.08 * ri);
+            self.b.push(0.25 - 0.05 * ri);
+            self.c.push(-65.0);
+            self.d.push(2.0);
+        }
+
+        self.weights.clear();
+        self.weights.reserve_exact(n * n);
+        for pre in 0..n {
+            for _ in 0..n {
+                let w = rng.uniform();
+                self.weights.push(if pre < ne { 0.5 * w } else { -w });
+            }
+        }
+
+        self.v = vec![0.0; n];
+        self.u = vec![0.0; n];
+        self.input = vec![0.0; n];
+        self.fired = Vec::with_capacity(n);
+        self.initial_rng = rng;
+        self.restart();
+    }
+
+    /// Returns to the initial state, `v = -65`, `u = b v`, with the noise,
+    /// spike counts, rates and filters from their start.
+    fn restart(&mut self) {
+        self.v.fill(-65.0);
+        for (u, b) in self.u.iter_mut().zip(&self.b) {
+            *u = b * -65.0;
+        }
+        self.rng = self.initial_rng.clone();
+        self.pending_ms = 0.0;
+        self.spikes_this_tick = 0;
+        self.total_spikes = 0;
+        self.rate_hz = 0.0;
+        self.rate_e_hz = 0.0;
+        self.rate_i_hz = 0.0;
+
+        let sample_hz = 1000.0 / STEP_MS;
+        self.alpha = BandPass::new(8.0, 12.0, sample_hz);
+        self.gamma = BandPass::new(30.0, 80.0, sample_hz);
+        self.alpha_hz = 0.0;
+        self.gamma_hz = 0.0;
+    }
+
+    /// One 1 ms update of the paper's main loop.
+    fn step(&mut self) -> (usize, usize) {
+        let (ne, n) = (self.ne, self.len());
+
+        self.fired.clear();
+        for k in 0..n {
+            if self.v[k] >= 30.0 {
+                self.fired.push(k);
+                self.v[k] = self.c[k];
+                self.u[k] += self.d[k];
+            }
+        }
+
+        for k in 0..n {
+            let sigma = if k < ne { self.noise_e } else { self.noise_i };
+            self.input[k] = self.i_ext + sigma * self.rng.normal();
+        }
+        for &pre in &self.fired {
+            let column = &self.weights[pre * n..(pre + 1) * n];
+            for (input, w) in self.input.iter_mut().zip(column) {
+                *input += w;
+            }
+        }
+
+        for k in 0..n {
+            let f = Equations::STANDARD.rhs(self.a[k], self.b[k], self.input[k]);
+            (self.v[k], self.u[k]) = Integrator::SplitEuler.step(f, self.v[k], self.u[k], STEP_MS);
+        }
+
+        let fired_e = self.fired.iter().take_while(|&&k| k < ne).count();
+        (fired_e, self.fired.len() - fired_e)
+    }
+
+    fn rate_hz(count: usize, size: usize) -> f64 {
+        if size == 0 {
+            0.0
+        } else {
+            count as f64 / size as f64 * 1000.0 / STEP_MS
+        }
+    }
+}
+
+impl PluginRuntime for Izhikevich2003Network {
+    fn set_config_value(&mut self, key: &str, value: &Value) {
+        let Some(x) = value.as_f64().filter(|x| x.is_finite()) else {
+            return;
+        };
+        match key {
+            "seed" => {
+                self.seed = x.max(0.0) as u64;
+                self.build();
+            }
+            "ne" => {
+                self.ne = (x.max(0.0) as usize).min(MAX_NEURONS - self.ni);
+                self.build();
+            }
+            "ni" => {
+                self.ni = (x.max(0.0) as usize).min(MAX_NEURONS - self.ne);
+                self.build();
+            }
+            "noise_e" => self.noise_e = x,
+            "noise_i" => self.noise_i = x,
+            "rate_tau_ms" if x > 0.0 => self.rate_tau_ms = x,
+            _ => {}
+        }
+    }
+
+    fn set_input_value(&mut self, key: &str, v: f64) {
+        if key == "i_syn" {
+            self.i_ext = if v.is_finite() { v } else { 0.0 };
+        }
+    }
+
+    fn process_tick(&mut self, tick: u64, period_seconds: f64) {
+        if !period_seconds.is_finite() || period_seconds <= 0.0 {
+            return;
+        }
+        // The host restarts its tick count on a restart.
+        if self.last_tick.is_some_and(|last| tick < last) {
+            self.restart();
+        }
+        self.last_tick = Some(tick);
+
+        self.spikes_this_tick = 0;
+        self.pending_ms += period_seconds * 1000.0;
+        let smoothing = (STEP_MS / self.rate_tau_ms).min(1.0);
+
+        while self.pending_ms >= STEP_MS {
+            self.pending_ms -= STEP_MS;
+            let (fired_e, fired_i) = self.step();
+            let fired = fired_e + fired_i;
+            self.spikes_this_tick += fired as u32;
+            self.total_spikes += fired as u64;
+
+            let rate = Self::rate_hz(fired, self.len());
+            self.rate_hz += (rate - self.rate_hz) * smoothing;
+            self.rate_e_hz += (Self::rate_hz(fired_e, self.ne) - self.rate_e_hz) * smoothing;
+            self.rate_i_hz += (Self::rate_hz(fired_i, self.ni) - self.rate_i_hz) * smoothing;
+            self.alpha_hz = self.alpha.process(rate);
+            self.gamma_hz = self.gamma.process(rate);
+        }
+    }
+
+    fn get_output_value(&self, key: &str) -> f64 {
+        match key {
+            "Spikes this tick" => f64::from(self.spikes_this_tick),
+            "Total spikes" => self.total_spikes as f64,
+            "Population rate (Hz)" => self.rate_hz,
+            "Excitatory rate (Hz)" => self.rate_e_hz,
+            "Inhibitory rate (Hz)" => self.rate_i_hz,
+            "Mean membrane potential (mV)" => self.v.iter().sum::<f64>() / self.len().max(1) as f64,
+            "Alpha rhythm (8-12 Hz)" => self.alpha_hz,
+            "Gamma rhythm (30-80 Hz)" => self.gamma_hz,
+            _ => 0.0,
+        }
+    }
+
+    fn get_internal_value(&self, key: &str) -> Option<f64> {
+        match key {
+            "n" => Some(self.len() as f64),
+            "mean_u" => Some(self.u.iter().sum::<f64>() / self.len().max(1) as f64),
+            _ => None,
+        }
+    }
+}
+
+rtsyn_plugin::export_plugin!(Izhikevich2003Network);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Spikes of each 1 ms tick.
+    fn run(network: &mut Izhikevich2003Network, ticks: u64) -> Vec<f64> {
+        (0..ticks)
+            .map(|tick| {
+                network.process_tick(tick, 1e-3);
+                network.get_output_value("Spikes this tick")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn default_network_fires_sparsely() {
+        let mut network = Izhikevich2003Network::default();
+        let spikes = run(&mut network, 1000);
+        let total: f64 = spikes.iter().sum();
+        assert_eq!(network.get_output_value("Total spikes"), total);
+        // Mean rate of the 1000 neurons over the second, in Hz.
+        assert!((2.0..30.0).contains(&(total / 1000.0)), "{total}");
+    }
+
+    #[test]
+    fn restart_repeats_the_first_run() {
+        let mut network = Izhikevich2003Network::default();
+        network.set_config_value("ne", &Value::from(80));
+        network.set_config_value("ni", &Value::from(20));
+        let first = run(&mut network, 500);
+        assert_eq!(run(&mut network, 500), first);
+
+        network.set_config_value("seed", &Value::from(2));
+        assert_ne!(run(&mut network, 500), first);
+    }
+
+    #[test]
+    fn size_is_capped() {
+        let mut network = Izhikevich2003Network::default();
+        network.set_config_value("ne", &Value::from(1e9));
+        assert_eq!(network.len(), MAX_NEURONS);
+        assert_eq!(network.weights.len(), MAX_NEURONS * MAX_NEURONS);
+    }
+}
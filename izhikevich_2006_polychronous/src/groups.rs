@@ -0,0 +1,270 @@
+//! Search for polychronous groups, after the `polychron` program that
+//! accompanies Izhikevich (2006): every triplet of strong excitatory inputs
+//! to a neuron is fired so that its spikes arrive together, and the noiseless
+//! network, restricted to strong excitatory synapses, is run to see how far
+//! the activity propagates.
+
+use std::collections::HashSet;
+
+use izhikevich_2003_neuron_rust::integrator::Integrator;
+use izhikevich_2003_neuron_rust::model::Equations;
+use serde_json::{json, Value};
+
+use crate::{Izhikevich2006Network, B, C, STEP_MS};
+
+// Duration of each trial run.
+const TRIAL_MS: usize = 100;
+// Strong inputs considered per neuron; triplets grow with the cube of it.
+const MAX_ANCHORS: usize = 8;
+// A spike arriving this long before a neuron fires still counts as a cause.
+const CAUSAL_WINDOW_MS: usize = 10;
+
+/// Spikes set off by one anchor triplet, as `(neuron, time in ms)`.
+#[derive(Debug, Clone)]
+pub struct Group {
+    mother: usize,
+    anchors: Vec<(usize, usize)>,
+    spikes: Vec<(usize, usize)>,
+    layers: usize,
+}
+
+impl Group {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "mother": self.mother,
+            "anchors": self.anchors,
+            "spikes": self.spikes,
+            "layers": self.layers,
+        })
+    }
+}
+
+/// The parts of the network the search reads, copied so that it can run on a
+/// worker thread while the network goes on.
+pub struct Wiring {
+    ne: usize,
+    m: usize,
+    max_delay: usize,
+    a: Vec<f64>,
+    d: Vec<f64>,
+    post: Vec<usize>,
+    delay: Vec<usize>,
+    weights: Vec<f64>,
+    incoming: Vec<Vec<usize>>,
+    strong_weight: f64,
+    min_layers: usize,
+}
+
+impl Wiring {
+    pub fn of(net: &Izhikevich2006Network) -> Self {
+        Self {
+            ne: net.ne,
+            m: net.m,
+            max_delay: net.max_delay,
+            a: net.a.clone(),
+            d: net.d.clone(),
+            post: net.post.clone(),
+            delay: net.delay.clone(),
+            weights: net.weights.clone(),
+            incoming: net.incoming.clone(),
+            strong_weight: net.group_strength * net.w_max,
+            min_layers: net.min_group_layers,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.a.len()
+    }
+
+    fn strong(&self, syn: usize) -> bool {
+        syn < self.ne * self.m && self.weights[syn] >= self.strong_weight
+    }
+}
+
+/// Trial state, allocated once per search and reset between trials.
+struct Trial {
+    v: Vec<f64>,
+    u: Vec<f64>,
+    input: Vec<f64>,
+    active: Vec<usize>,
+    is_active: Vec<bool>,
+    // Layer and time of the latest causal input of each neuron.
+    input_layer: Vec<usize>,
+    input_ms: Vec<usize>,
+    // Spikes to deliver at each time step: `(post, weight, layer)`.
+    arrivals: Vec<Vec<(usize, f64, usize)>>,
+    spikes: Vec<(usize, usize, usize)>,
+}
+
+pub fn detect(net: &Wiring) -> Vec<Group> {
+    let (ne, m) = (net.ne, net.m);
+    let mut trial = Trial::new(net);
+
+    let mut groups = Vec::new();
+    let mut seen = HashSet::new();
+    for mother in 0..ne {
+        let anchors: Vec<usize> = net.incoming[mother]
+            .iter()
+            .copied()
+            .filter(|&syn| net.strong(syn))
+            .take(MAX_ANCHORS)
+            .collect();
+
+        for i in 0..anchors.len() {
+            for j in i + 1..anchors.len() {
+                for k in j + 1..anchors.len() {
+                    let triplet = [anchors[i], anchors[j], anchors[k]];
+                    let latest = triplet.iter().map(|&syn| net.delay[syn]).max();
+                    let latest = latest.unwrap_or(0);
+                    let fire: Vec<(usize, usize)> = triplet
+                        .iter()
+                        .map(|&syn| (syn / m, latest - net.delay[syn]))
+                        .collect();
+
+                    trial.run(net, &fire);
+                    let layers = trial.spikes.iter().map(|s| s.2).max().unwrap_or(0);
+                    let mother_fired = trial.spikes.iter().any(|s| s.0 == mother);
+                    if !mother_fired || layers < net.min_layers {
+                        continue;
+                    }
+
+                    let mut spikes: Vec<(usize, usize)> =
+                        trial.spikes.iter().map(|&(k, t, _)| (k, t)).collect();
+                    spikes.sort_by_key(|&(k, t)| (t, k));
+                    if seen.insert(spikes.clone()) {
+                        groups.push(Group {
+                            mother,
+                            anchors: fire,
+                            spikes,
+                            layers,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    groups
+}
+
+impl Trial {
+    fn new(net: &Wiring) -> Self {
+        let n = net.len();
+        let (v_rest, u_rest) = Trial::rest();
+        Self {
+            v: vec![v_rest; n],
+            u: vec![u_rest; n],
+            input: vec![0.0; n],
+            active: Vec::new(),
+            is_active: vec![false; n],
+            input_layer: vec![0; n],
+            input_ms: vec![0; n],
+            arrivals: vec![Vec::new(); TRIAL_MS + net.max_delay + 1],
+            spikes: Vec::new(),
+        }
+    }
+
+    fn rest() -> (f64, f64) {
+        Equations::STANDARD.rest(B).unwrap_or((C, B * C))
+    }
+
+    fn run(&mut self, net: &Wiring, anchors: &[(usize, usize)]) {
+        // Only neurons that received input were touched by the last trial.
+        let (v_rest, u_rest) = Trial::rest();
+        for &k in &self.active {
+            self.v[k] = v_rest;
+            self.u[k] = u_rest;
+            self.input[k] = 0.0;
+            self.is_active[k] = false;
+            self.input_layer[k] = 0;
+            self.input_ms[k] = 0;
+        }
+        self.active.clear();
+        self.arrivals.iter_mut().for_each(Vec::clear);
+        self.spikes.clear();
+
+        for t in 0..TRIAL_MS {
+            for &(k, t_fire) in anchors {
+                if t_fire == t {
+                    self.fire(net, k, t, 1);
+                }
+            }
+
+            for (post, w, layer) in std::mem::take(&mut self.arrivals[t]) {
+                if anchors.iter().any(|&(k, _)| k == post) {
+                    continue;
+                }
+                if !self.is_active[post] {
+                    self.is_active[post] = true;
+                    self.active.push(post);
+                }
+                self.input[post] += w;
+                if t - self.input_ms[post] > CAUSAL_WINDOW_MS || layer > self.input_layer[post] {
+                    self.input_layer[post] = layer;
+                }
+                self.input_ms[post] = t;
+            }
+
+            for index in 0..self.active.len() {
+                let k = self.active[index];
+                let f = Equations::STANDARD.rhs(net.a[k], B, self.input[k]);
+                (self.v[k], self.u[k]) =
+                    Integrator::SplitEuler.step(f, self.v[k], self.u[k], STEP_MS);
+                self.input[k] = 0.0;
+                if self.v[k] >= 30.0 {
+                    self.v[k] = C;
+                    self.u[k] += net.d[k];
+                    let layer = self.input_layer[k] + 1;
+                    self.fire(net, k, t + 1, layer);
+                }
+            }
+        }
+    }
+
+    fn fire(&mut self, net: &Wiring, k: usize, t: usize, layer: usize) {
+        self.spikes.push((k, t, layer));
+        let synapses = k * net.m..(k + 1) * net.m;
+        for syn in synapses.filter(|&syn| net.strong(syn)) {
+            // Delivered as in the network, `delay - 1` ms after firing.
+            if let Some(slot) = self.arrivals.get_mut(t + net.delay[syn] - 1) {
+                slot.push((net.post[syn], net.weights[syn], layer));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rtsyn_plugin::prelude::*;
+    use serde_json::Value;
+
+    use super::*;
+
+    #[test]
+    fn trials_do_not_depend_on_earlier_ones() {
+        let mut net = Izhikevich2006Network::default();
+        for (key, value) in [
+            ("ne", 80.0),
+            ("ni", 20.0),
+            ("m", 10.0),
+            ("w_exc", 10.0),
+            ("min_group_layers", 1.0),
+        ] {
+            net.set_config_value(key, &Value::from(value));
+        }
+
+        let wiring = Wiring::of(&net);
+        let groups = detect(&wiring);
+        assert!(!groups.is_empty());
+
+        for group in &groups {
+            let mut fresh = Trial::new(&wiring);
+            fresh.run(&wiring, &group.anchors);
+            let mut spikes: Vec<(usize, usize)> =
+                fresh.spikes.iter().map(|&(k, t, _)| (k, t)).collect();
+            spikes.sort_by_key(|&(k, t)| (t, k));
+            let layers = fresh.spikes.iter().map(|s| s.2).max().unwrap_or(0);
+            assert_eq!(spikes, group.spikes);
+            assert_eq!(layers, group.layers);
+        }
+    }
+}
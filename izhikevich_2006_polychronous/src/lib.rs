@@ -0,0 +1,744 @@
+mod groups;
+
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+use izhikevich_2003_neuron_rust::integrator::Integrator;
+use izhikevich_2003_neuron_rust::model::Equations;
+use izhikevich_2003_neuron_rust::rng::Rng;
+use rtsyn_plugin::prelude::*;
+use serde_json::Value;
+
+// The network is updated in 1 ms steps, as in the paper's C++ code.
+const STEP_MS: f64 = 1.0;
+const MAX_NEURONS: usize = 10_000;
+const MAX_DELAY_MS: usize = 50;
+const RASTER_SLOTS: usize = 8;
+// `b` and `c` are shared by both populations.
+const B: f64 = 0.2;
+const C: f64 = -65.0;
+// Weights are consolidated from their derivatives once per simulated second.
+const CONSOLIDATION_MS: u64 = 1000;
+
+/// Spiking network with axonal conduction delays and STDP of Izhikevich
+/// (2006): `ne` regular-spiking excitatory and `ni` fast-spiking inhibitory
+/// neurons, `m` synapses each, driven by random thalamic input.
+#[derive(Debug)]
+struct Izhikevich2006Network {
+    ne: usize,
+    ni: usize,
+    // Configured synapses per neuron, and the number the current sizes allow.
+    configured_m: usize,
+    m: usize,
+    max_delay: usize,
+    seed: u64,
+    stdp: bool,
+    w_max: f64,
+    w_exc: f64,
+    w_inh: f64,
+    thalamic_amp: f64,
+    rate_tau_ms: f64,
+    group_strength: f64,
+    min_group_layers: usize,
+    i_ext: f64,
+    v: Vec<f64>,
+    u: Vec<f64>,
+    a: Vec<f64>,
+    d: Vec<f64>,
+    // Synapse `j` of neuron `pre` is at `pre * m + j`; each neuron's synapses
+    // are sorted by delay, and those with delay `k` ms are
+    // `delay_start[pre * (max_delay + 1) + k - 1]..delay_start[.. + k]`.
+    post: Vec<usize>,
+    delay: Vec<usize>,
+    weights: Vec<f64>,
+    weight_derivs: Vec<f64>,
+    delay_start: Vec<usize>,
+    // Excitatory synapses onto each neuron.
+    incoming: Vec<Vec<usize>>,
+    // Presynaptic STDP trace of the last `max_delay + 1` ms, as a ring:
+    // `ltp[(t % (max_delay + 1)) * n + k]`.
+    ltp: Vec<f64>,
+    ltd: Vec<f64>,
+    input: Vec<f64>,
+    // Spikes of the last `max_delay` updates still travelling down their axons.
+    recent: VecDeque<(u64, usize)>,
+    fired: Vec<usize>,
+    rng: Rng,
+    // Thalamic input generator as left by `build`, for restarts.
+    initial_rng: Rng,
+    t_ms: u64,
+    pending_ms: f64,
+    spikes_this_tick: u32,
+    total_spikes: u64,
+    raster: [f64; RASTER_SLOTS],
+    rate_hz: f64,
+    groups_found: usize,
+    // Group search running on a worker thread: the number of groups it wrote.
+    dump: Option<Receiver<Result<usize, String>>>,
+    dump_status: DumpStatus,
+    last_tick: Option<u64>,
+}
+
+/// Outcome of the last `dump_groups`, shown as the `dump_status` internal
+/// variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DumpStatus {
+    Idle,
+    Pending,
+    Done,
+    Failed,
+}
+
+impl DumpStatus {
+    fn value(self) -> f64 {
+        match self {
+            Self::Idle => 0.0,
+            Self::Pending => 1.0,
+            Self::Done => 2.0,
+            Self::Failed => -1.0,
+        }
+    }
+}
+
+impl Default for Izhikevich2006Network {
+    fn default() -> Self {
+        let mut network = Self {
+            ne: 800,
+            ni: 200,
+            configured_m: 100,
+            m: 100,
+            max_delay: 20,
+            seed: 1,
+            stdp: true,
+            w_max: 10.0,
+            w_exc: 6.0,
+            w_inh: -5.0,
+            thalamic_amp: 20.0,
+            rate_tau_ms: 10.0,
+            group_strength: 0.95,
+            min_group_layers: 7,
+            i_ext: 0.0,
+            v: Vec::new(),
+            u: Vec::new(),
+            a: Vec::new(),
+            d: Vec::new(),
+            post: Vec::new(),
+            delay: Vec::new(),
+            weights: Vec::new(),
+            weight_derivs: Vec::new(),
+            delay_start: Vec::new(),
+            incoming: Vec::new(),
+            ltp: Vec::new(),
+            ltd: Vec::new(),
+            input: Vec::new(),
+            recent: VecDeque::new(),
+            fired: Vec::new(),
+            rng: Rng::new(1),
+            initial_rng: Rng::new(1),
+            t_ms: 0,
+            pending_ms: 0.0,
+            spikes_this_tick: 0,
+            total_spikes: 0,
+            raster: [-1.0; RASTER_SLOTS],
+            rate_hz: 0.0,
+            groups_found: 0,
+            dump: None,
+            dump_status: DumpStatus::Idle,
+            last_tick: None,
+        };
+        network.build();
+        network
+    }
+}
+
+impl PluginDescriptor for Izhikevich2006Network {
+    fn name() -> &'static str {
+        "Izhikevich 2006 Polychronous Network"
+    }
+
+    fn kind() -> &'static str {
+        "izhikevich_2006_polychronous"
+    }
+
+    fn plugin_type() -> PluginType {
+        PluginType::Computational
+    }
+
+    fn inputs() -> &'static [&'static str] {
+        &["i_syn"]
+    }
+
+    fn outputs() -> &'static [&'static str] {
+        &[
+            "Spikes this tick",
+            "Total spikes",
+            "Population rate (Hz)",
+            "Mean membrane potential (mV)",
+            "Mean excitatory weight",
+            "Polychronous groups",
+            "Fired neuron 1",
+            "Fired neuron 2",
+            "Fired neuron 3",
+            "Fired neuron 4",
+            "Fired neuron 5",
+            "Fired neuron 6",
+            "Fired neuron 7",
+            "Fired neuron 8",
+        ]
+    }
+
+    fn internal_variables() -> &'static [&'static str] {
+        &["n", "time_s", "strong_synapses", "dump_status"]
+    }
+
+    fn default_vars() -> Vec<(&'static str, Value)> {
+        vec![
+            ("seed", 1.into()),
+            ("ne", 800.into()),
+            ("ni", 200.into()),
+            ("m", 100.into()),
+            ("max_delay_ms", 20.into()),
+            ("stdp", true.into()),
+            ("w_max", 10.0.into()),
+            ("w_exc", 6.0.into()),
+            ("w_inh", (-5.0).into()),
+            ("thalamic_amp", 20.0.into()),
+            ("rate_tau_ms", 10.0.into()),
+            ("group_strength", 0.95.into()),
+            ("min_group_layers", 7.into()),
+            ("dump_groups", "".into()),
+        ]
+    }
+
+    fn behavior() -> PluginBehavior {
+        PluginBehavior {
+            supports_start_stop: true,
+            supports_restart: true,
+            supports_apply: false,
+            extendable_inputs: ExtendableInputs::None,
+            loads_started: false,
+            external_window: false,
+            starts_expanded: true,
+            start_requires_connected_inputs: Vec::new(),
+            start_requires_connected_outputs: Vec::new(),
+        }
+    }
+}
+
+impl Izhikevich2006Network {
+    fn len(&self) -> usize {
+        self.ne + self.ni
+    }
+
+    /// Draws the connectivity from `seed` as in the paper: excitatory
+    /// neurons project to any other neuron with delays spread evenly over
+    /// `1..=max_delay` ms, inhibitory neurons project to excitatory ones
+    /// with a 1 ms delay.
+    fn build(&mut self) {
+        // Targets are distinct and never the neuron itself.
+        self.m = self.configured_m.min(self.ne).min(self.len() - 1);
+        let (ne, n, m, max_delay) = (self.ne, self.len(), self.m, self.max_delay);
+        let mut rng = Rng::new(self.seed);
+
+        self.a = (0..n).map(|k| if k < ne { 0.02 } else { 0.1 }).collect();
+        self.d = (0..n).map(|k| if k < ne { 8.0 } else { 2.0 }).collect();
+
+        self.post = Vec::with_capacity(n * m);
+        self.delay = Vec::with_capacity(n * m);
+        self.weights = Vec::with_capacity(n * m);
+        self.delay_start = Vec::with_capacity(n * (max_delay + 1));
+        // Holds `0..n` in order between neurons.
+        let mut candidates: Vec<usize> = (0..n).collect();
+        let mut swaps = Vec::with_capacity(m);
+        for pre in 0..n {
+            let pool = if pre < ne { n } else { ne };
+            let targets = draw_targets(&mut rng, &mut candidates[..pool], pre, m, &mut swaps);
+            self.post.extend_from_slice(targets);
+            undo_draw(&mut candidates[..pool], pre, &swaps);
+            for j in 0..m {
+                let delay = if pre < ne { 1 + j * max_delay / m } else { 1 };
+                self.delay.push(delay);
+                self.weights
+                    .push(if pre < ne { self.w_exc } else { self.w_inh });
+            }
+
+            let start = pre * m;
+            self.delay_start.push(start);
+            for k in 1..=max_delay {
+                let end = start + self.delay[start..start + m].partition_point(|&d| d <= k);
+                self.delay_start.push(end);
+            }
+        }
+        self.weight_derivs = vec![0.0; n * m];
+
+        self.incoming = vec![Vec::new(); n];
+        for syn in 0..ne * m {
+            self.incoming[self.post[syn]].push(syn);
+        }
+
+        self.v = vec![0.0; n];
+        self.u = vec![0.0; n];
+        self.ltp = vec![0.0; n * (max_delay + 1)];
+        self.ltd = vec![0.0; n];
+        self.input = vec![0.0; n];
+        self.recent = VecDeque::with_capacity(n * (max_delay + 1));
+        self.fired = Vec::with_capacity(n);
+        self.initial_rng = rng;
+        // A search still running describes the old network.
+        self.dump = None;
+        self.dump_status = DumpStatus::Idle;
+        self.groups_found = 0;
+        self.restart();
+    }
+
+    /// Returns to the initial state, `v = -65`, `u = b v`, with the
+    /// thalamic input from its start and no spikes in flight. Learned
+    /// weights are kept; their pending derivatives are dropped.
+    fn restart(&mut self) {
+        self.v.fill(-65.0);
+        self.u.fill(B * -65.0);
+        self.ltp.fill(0.0);
+        self.ltd.fill(0.0);
+        self.weight_derivs.fill(0.0);
+        self.recent.clear();
+        self.fired.clear();
+        self.rng = self.initial_rng.clone();
+        self.t_ms = 0;
+        self.pending_ms = 0.0;
+        self.spikes_this_tick = 0;
+        self.total_spikes = 0;
+        self.raster = [-1.0; RASTER_SLOTS];
+        self.rate_hz = 0.0;
+    }
+
+    /// Synapses of `pre` with a delay of `k` ms.
+    fn synapses_with_delay(&self, pre: usize, k: usize) -> std::ops::Range<usize> {
+        let row = pre * (self.max_delay + 1);
+        self.delay_start[row + k - 1]..self.delay_start[row + k]
+    }
+
+    /// One 1 ms update of the paper's main loop.
+    fn step(&mut self) {
+        let (ne, n, m) = (self.ne, self.len(), self.m);
+        let t = self.t_ms;
+        let ring = self.max_delay as u64 + 1;
+        let slot = (t % ring) as usize * n;
+
+        self.input.fill(self.i_ext);
+        let thalamic = ((self.rng.uniform() * n as f64) as usize).min(n - 1);
+        self.input[thalamic] += self.thalamic_amp;
+
+        self.fired.clear();
+        for k in 0..n {
+            if self.v[k] < 30.0 {
+                continue;
+            }
+            self.v[k] = C;
+            self.u[k] += self.d[k];
+            self.ltp[slot + k] = 0.1;
+            self.ltd[k] = 0.12;
+            // Potentiation by the presynaptic traces at the spikes' arrival.
+            for &syn in &self.incoming[k] {
+                let arrival = ((t + ring - self.delay[syn] as u64) % ring) as usize;
+                self.weight_derivs[syn] += self.ltp[arrival * n + syn / m];
+            }
+            self.fired.push(k);
+            self.recent.push_back((t, k));
+        }
+
+        // As in spnet.cpp, a spike is delivered `delay - 1` ms after it fired,
+        // counting the update it fired in, and so drives the membrane from
+        // `delay` ms on. Arriving in the same update as a postsynaptic spike
+        // it is depressed, not potentiated.
+        while self
+            .recent
+            .front()
+            .is_some_and(|&(t_fired, _)| t - t_fired >= self.max_delay as u64)
+        {
+            self.recent.pop_front();
+        }
+        for &(t_fired, pre) in &self.recent {
+            let age = (t - t_fired) as usize;
+            for syn in self.synapses_with_delay(pre, age + 1) {
+                let post = self.post[syn];
+                self.input[post] += self.weights[syn];
+                // Depression by the postsynaptic trace at arrival.
+                if pre < ne {
+                    self.weight_derivs[syn] -= self.ltd[post];
+                }
+            }
+        }
+
+        for k in 0..n {
+            let f = Equations::STANDARD.rhs(self.a[k], B, self.input[k]);
+            (self.v[k], self.u[k]) = Integrator::SplitEuler.step(f, self.v[k], self.u[k], STEP_MS);
+        }
+
+        let next = ((t + 1) % ring) as usize * n;
+        for k in 0..n {
+            self.ltp[next + k] = 0.95 * self.ltp[slot + k];
+        }
+        self.ltd.iter_mut().for_each(|x| *x *= 0.95);
+
+        self.t_ms += 1;
+        if self.stdp && self.t_ms.is_multiple_of(CONSOLIDATION_MS) {
+            for (w, dw) in self.weights[..ne * m]
+                .iter_mut()
+                .zip(&mut self.weight_derivs[..ne * m])
+            {
+                *w = (*w + 0.01 + *dw).clamp(0.0, self.w_max);
+                *dw *= 0.9;
+            }
+        }
+    }
+
+    fn mean_excitatory_weight(&self) -> f64 {
+        let exc = &self.weights[..self.ne * self.m];
+        exc.iter().sum::<f64>() / exc.len().max(1) as f64
+    }
+
+    fn strong_synapses(&self) -> usize {
+        let threshold = self.group_strength * self.w_max;
+        self.weights[..self.ne * self.m]
+            .iter()
+            .filter(|&&w| w >= threshold)
+            .count()
+    }
+
+    /// Starts the group search on a copy of the current wiring, on a worker
+    /// thread that writes the groups to `path` as JSON. The network keeps
+    /// running meanwhile; `process_tick` collects the result.
+    fn dump_groups(&mut self, path: String) {
+        let wiring = groups::Wiring::of(self);
+        let header = serde_json::json!({
+            "kind": Self::kind(),
+            "seed": self.seed,
+            "time_s": self.t_ms as f64 / 1000.0,
+            "group_strength": self.group_strength,
+            "min_group_layers": self.min_group_layers,
+        });
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let groups = groups::detect(&wiring);
+            let mut dump = header;
+            dump["groups"] = groups.iter().map(groups::Group::to_json).collect();
+            let written = serde_json::to_string_pretty(&dump)
+                .map_err(|e| e.to_string())
+                .and_then(|text| std::fs::write(&path, text).map_err(|e| format!("{path}: {e}")))
+                .map(|()| groups.len());
+            let _ = sender.send(written);
+        });
+        self.dump = Some(receiver);
+        self.dump_status = DumpStatus::Pending;
+    }
+
+    /// Collects the result of a finished group search.
+    fn poll_dump(&mut self) {
+        let Some(dump) = &self.dump else {
+            return;
+        };
+        let result = match dump.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => Err("group search stopped".into()),
+        };
+        self.dump = None;
+        match result {
+            Ok(found) => {
+                self.groups_found = found;
+                self.dump_status = DumpStatus::Done;
+            }
+            Err(_) => self.dump_status = DumpStatus::Failed,
+        }
+    }
+}
+
+/// Draws `m` distinct targets other than `pre` from `candidates`, which holds
+/// `0..pool` in order, by a partial Fisher-Yates shuffle. The swaps are kept
+/// in `swaps` for [`undo_draw`].
+fn draw_targets<'a>(
+    rng: &mut Rng,
+    candidates: &'a mut [usize],
+    pre: usize,
+    m: usize,
+    swaps: &mut Vec<usize>,
+) -> &'a [usize] {
+    let mut len = candidates.len();
+    // A neuron never projects to itself: move it out of the range drawn from.
+    if pre < len {
+        candidates.swap(pre, len - 1);
+        len -= 1;
+    }
+    swaps.clear();
+    for j in 0..m {
+        let r = j + ((rng.uniform() * (len - j) as f64) as usize).min(len - j - 1);
+        candidates.swap(j, r);
+        swaps.push(r);
+    }
+    &candidates[..m]
+}
+
+/// Puts `candidates` back in order after [`draw_targets`], in `O(m)`.
+fn undo_draw(candidates: &mut [usize], pre: usize, swaps: &[usize]) {
+    for (j, &r) in swaps.iter().enumerate().rev() {
+        candidates.swap(j, r);
+    }
+    let len = candidates.len();
+    if pre < len {
+        candidates.swap(pre, len - 1);
+    }
+}
+
+impl PluginRuntime for Izhikevich2006Network {
+    fn set_config_value(&mut self, key: &str, value: &Value) {
+        if let Some(path) = value.as_str() {
+            if key == "dump_groups" && !path.trim().is_empty() {
+                self.dump_groups(path.trim().to_string());
+            }
+            return;
+        }
+        if let (Some(on), "stdp") = (value.as_bool(), key) {
+            self.stdp = on;
+            return;
+        }
+
+        let Some(x) = value.as_f64().filter(|x| x.is_finite()) else {
+            return;
+        };
+        let count = x.max(0.0) as usize;
+        match key {
+            "seed" => {
+                self.seed = x.max(0.0) as u64;
+                self.build();
+            }
+            "ne" if count > 0 => {
+                self.ne = count.min(MAX_NEURONS - self.ni);
+                self.build();
+            }
+            "ni" => {
+                self.ni = count.min(MAX_NEURONS - self.ne);
+                self.build();
+            }
+            "m" if count > 0 => {
+                self.configured_m = count;
+                self.build();
+            }
+            "max_delay_ms" if count > 0 => {
+                self.max_delay = count.min(MAX_DELAY_MS);
+                self.build();
+            }
+            "stdp" => self.stdp = x != 0.0,
+            "w_max" if x > 0.0 => self.w_max = x,
+            "w_exc" => {
+                self.w_exc = x;
+                self.build();
+            }
+            "w_inh" => {
+                self.w_inh = x;
+                self.build();
+            }
+            "thalamic_amp" => self.thalamic_amp = x,
+            "rate_tau_ms" if x > 0.0 => self.rate_tau_ms = x,
+            "group_strength" if x > 0.0 => self.group_strength = x,
+            "min_group_layers" => self.min_group_layers = count,
+            _ => {}
+        }
+    }
+
+    fn set_input_value(&mut self, key: &str, v: f64) {
+        if key == "i_syn" {
+            self.i_ext = if v.is_finite() { v } else { 0.0 };
+        }
+    }
+
+    fn process_tick(&mut self, tick: u64, period_seconds: f64) {
+        if !period_seconds.is_finite() || period_seconds <= 0.0 {
+            return;
+        }
+        self.poll_dump();
+        // The host restarts its tick count on a restart.
+        if self.last_tick.is_some_and(|last| tick < last) {
+            self.restart();
+        }
+        self.last_tick = Some(tick);
+
+        self.spikes_this_tick = 0;
+        self.raster = [-1.0; RASTER_SLOTS];
+        self.pending_ms += period_seconds * 1000.0;
+        let smoothing = (STEP_MS / self.rate_tau_ms).min(1.0);
+
+        while self.pending_ms >= STEP_MS {
+            self.pending_ms -= STEP_MS;
+            self.step();
+
+            let free = self.raster.iter().position(|&k| k < 0.0);
+            if let Some(free) = free {
+                for (slot, &k) in self.raster[free..].iter_mut().zip(&self.fired) {
+                    *slot = k as f64;
+                }
+            }
+            let fired = self.fired.len();
+            self.spikes_this_tick += fired as u32;
+            self.total_spikes += fired as u64;
+
+            let rate = fired as f64 / self.len() as f64 * 1000.0 / STEP_MS;
+            self.rate_hz += (rate - self.rate_hz) * smoothing;
+        }
+    }
+
+    fn get_output_value(&self, key: &str) -> f64 {
+        match key {
+            "Spikes this tick" => f64::from(self.spikes_this_tick),
+            "Total spikes" => self.total_spikes as f64,
+            "Population rate (Hz)" => self.rate_hz,
+            "Mean membrane potential (mV)" => self.v.iter().sum::<f64>() / self.len() as f64,
+            "Mean excitatory weight" => self.mean_excitatory_weight(),
+            "Polychronous groups" => self.groups_found as f64,
+            // "Fired neuron <j>": index of the j-th neuron that fired this tick, or -1.
+            _ => key
+                .strip_prefix("Fired neuron ")
+                .and_then(|j| j.parse::<usize>().ok())
+                .and_then(|j| self.raster.get(j.checked_sub(1)?).copied())
+                .unwrap_or(-1.0),
+        }
+    }
+
+    fn get_internal_value(&self, key: &str) -> Option<f64> {
+        match key {
+            "n" => Some(self.len() as f64),
+            "time_s" => Some(self.t_ms as f64 / 1000.0),
+            "strong_synapses" => Some(self.strong_synapses() as f64),
+            "dump_status" => Some(self.dump_status.value()),
+            _ => None,
+        }
+    }
+}
+
+rtsyn_plugin::export_plugin!(Izhikevich2006Network);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small_network() -> Izhikevich2006Network {
+        let mut network = Izhikevich2006Network::default();
+        for (key, value) in [("ne", 80.0), ("ni", 20.0), ("m", 10.0)] {
+            network.set_config_value(key, &Value::from(value));
+        }
+        network
+    }
+
+    #[test]
+    fn connectivity_follows_the_paper() {
+        let network = Izhikevich2006Network::default();
+        let (ne, n, m) = (network.ne, network.len(), network.m);
+        for pre in 0..n {
+            let targets = &network.post[pre * m..(pre + 1) * m];
+            let mut distinct = targets.to_vec();
+            distinct.sort_unstable();
+            distinct.dedup();
+            assert_eq!(distinct.len(), m, "{pre}");
+            assert!(!targets.contains(&pre));
+            if pre >= ne {
+                assert!(targets.iter().all(|&post| post < ne));
+            }
+            for k in 1..=network.max_delay {
+                let with_delay = network.synapses_with_delay(pre, k);
+                let expected = if pre < ne {
+                    m / network.max_delay
+                } else if k == 1 {
+                    m
+                } else {
+                    0
+                };
+                assert_eq!(with_delay.len(), expected, "{pre} {k}");
+                assert!(with_delay.clone().all(|syn| network.delay[syn] == k));
+            }
+        }
+        for (post, incoming) in network.incoming.iter().enumerate() {
+            assert!(incoming
+                .iter()
+                .all(|&syn| syn < ne * m && network.post[syn] == post));
+        }
+        let incoming: usize = network.incoming.iter().map(Vec::len).sum();
+        assert_eq!(incoming, ne * m);
+    }
+
+    #[test]
+    fn targets_are_drawn_uniformly() {
+        let mut rng = Rng::new(3);
+        let (pool, m, pre) = (10, 3, 4);
+        let mut candidates: Vec<usize> = (0..pool).collect();
+        let mut swaps = Vec::new();
+        let mut counts = [0usize; 10];
+        for _ in 0..30_000 {
+            let targets = draw_targets(&mut rng, &mut candidates, pre, m, &mut swaps);
+            targets.iter().for_each(|&k| counts[k] += 1);
+            undo_draw(&mut candidates, pre, &swaps);
+            assert!(candidates.iter().copied().eq(0..pool));
+        }
+        assert_eq!(counts[pre], 0);
+        // 30000 draws of 3 out of 9 candidates: 10000 each.
+        let uniform = counts
+            .iter()
+            .enumerate()
+            .all(|(k, &c)| k == pre || c.abs_diff(10_000) < 400);
+        assert!(uniform, "{counts:?}");
+    }
+
+    #[test]
+    fn restart_repeats_the_run_with_the_learned_weights() {
+        let mut network = small_network();
+        network.set_config_value("stdp", &Value::from(false));
+        let run = |network: &mut Izhikevich2006Network| -> Vec<f64> {
+            (0..2000)
+                .map(|tick| {
+                    network.process_tick(tick, 1e-3);
+                    network.get_output_value("Fired neuron 1")
+                })
+                .collect()
+        };
+        let first = run(&mut network);
+        assert!(network.total_spikes > 0);
+        assert_eq!(run(&mut network), first);
+
+        network.set_config_value("stdp", &Value::from(true));
+        run(&mut network);
+        let learned = network.weights.clone();
+        network.process_tick(0, 1e-3);
+        assert_eq!(network.t_ms, 1);
+        assert_eq!(network.weights, learned);
+        assert_eq!(network.get_output_value("Fired neuron 9"), -1.0);
+    }
+
+    #[test]
+    fn groups_are_dumped_off_the_tick() {
+        let mut network = small_network();
+        network.set_config_value("w_exc", &Value::from(10.0));
+        network.set_config_value("min_group_layers", &Value::from(1.0));
+        let path =
+            std::env::temp_dir().join(format!("polychronous_groups_{}.json", std::process::id()));
+        network.set_config_value(
+            "dump_groups",
+            &Value::from(path.to_string_lossy().into_owned()),
+        );
+        assert_eq!(network.get_internal_value("dump_status"), Some(1.0));
+
+        let mut tick = 0;
+        while network.get_internal_value("dump_status") == Some(1.0) {
+            assert!(tick < 10_000, "group search did not finish");
+            network.process_tick(tick, 1e-3);
+            tick += 1;
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        assert_eq!(network.get_internal_value("dump_status"), Some(2.0));
+        let dump: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let found = dump["groups"].as_array().unwrap().len();
+        assert!(found > 0);
+        assert_eq!(
+            network.get_output_value("Polychronous groups"),
+            found as f64
+        );
+    }
+}
@@ -0,0 +1,349 @@
+mod presets;
+
+use izhikevich_2003_neuron_rust::integrator::{Integrator, Stepper, DEFAULT_STEP_MS};
+use presets::Preset;
+use rtsyn_plugin::prelude::*;
+use serde_json::Value;
+
+/// Recovery nullcline `U(v)` in `u' = a (U(v) - u)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Nullcline {
+    /// `U(v) = b (v - vr)`
+    Linear,
+    /// `U(v) = b (v - vb)^3` above `vb`, `0` below (fast-spiking interneurons).
+    Cubic { vb: f64 },
+    /// `U(v) = b (v - vr)` with `b` replaced by `b_below` under `vb`
+    /// (thalamic relay cells).
+    Switched { vb: f64, b_below: f64 },
+}
+
+/// Izhikevich (2007) simple model in physical units: `v` in mV, `u` and `I`
+/// in pA, `C` in pF, `k` in nS/mV, time in ms.
+#[derive(Debug)]
+struct Izhikevich2007Neuron {
+    i_inj: f64,
+    v: f64,
+    u: f64,
+    // Initial conditions a host restart returns to.
+    v0: f64,
+    u0: f64,
+    last_tick: Option<u64>,
+    cap: f64,
+    k: f64,
+    vr: f64,
+    vt: f64,
+    vpeak: f64,
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    // State-dependent peak and reset, `vpeak + vpeak_u u` and `c + c_u u`.
+    vpeak_u: f64,
+    c_u: f64,
+    nullcline: Nullcline,
+    v_mv: f64,
+    spikes_this_tick: u32,
+    total_spikes: u64,
+    stepper: Stepper,
+}
+
+impl Default for Izhikevich2007Neuron {
+    fn default() -> Self {
+        Self {
+            i_inj: 0.0,
+            v: -60.0,
+            u: 0.0,
+            v0: -60.0,
+            u0: 0.0,
+            last_tick: None,
+            cap: 100.0,
+            k: 0.7,
+            vr: -60.0,
+            vt: -40.0,
+            vpeak: 35.0,
+            a: 0.03,
+            b: -2.0,
+            c: -50.0,
+            d: 100.0,
+            vpeak_u: 0.0,
+            c_u: 0.0,
+            nullcline: Nullcline::Linear,
+            v_mv: -60.0,
+            spikes_this_tick: 0,
+            total_spikes: 0,
+            stepper: Stepper::default(),
+        }
+    }
+}
+
+impl PluginDescriptor for Izhikevich2007Neuron {
+    fn name() -> &'static str {
+        "Izhikevich 2007 Neuron"
+    }
+
+    fn kind() -> &'static str {
+        "izhikevich_2007_neuron"
+    }
+
+    fn plugin_type() -> PluginType {
+        PluginType::Computational
+    }
+
+    fn inputs() -> &'static [&'static str] {
+        &["Injected current (pA)"]
+    }
+
+    fn outputs() -> &'static [&'static str] {
+        &[
+            "Membrane potential (V)",
+            "Membrane potential (mV)",
+            "Recovery current (pA)",
+            "Spike",
+            "Spikes this tick",
+            "Total spikes",
+        ]
+    }
+
+    fn internal_variables() -> &'static [&'static str] {
+        &["v", "u"]
+    }
+
+    fn default_vars() -> Vec<(&'static str, Value)> {
+        vec![
+            ("preset", "custom".into()),
+            ("v", (-60.0).into()),
+            ("u", 0.0.into()),
+            ("C", 100.0.into()),
+            ("k", 0.7.into()),
+            ("vr", (-60.0).into()),
+            ("vt", (-40.0).into()),
+            ("vpeak", 35.0.into()),
+            ("a", 0.03.into()),
+            ("b", (-2.0).into()),
+            ("c", (-50.0).into()),
+            ("d", 100.0.into()),
+            ("integrator", "euler".into()),
+            ("step_ms", DEFAULT_STEP_MS.into()),
+            ("tolerance", 1e-3.into()),
+        ]
+    }
+
+    fn behavior() -> PluginBehavior {
+        PluginBehavior {
+            supports_start_stop: true,
+            supports_restart: true,
+            supports_apply: false,
+            extendable_inputs: ExtendableInputs::None,
+            loads_started: false,
+            external_window: false,
+            starts_expanded: true,
+            start_requires_connected_inputs: Vec::new(),
+            start_requires_connected_outputs: Vec::new(),
+        }
+    }
+}
+
+impl Izhikevich2007Neuron {
+    fn apply_preset(&mut self, preset: &Preset) {
+        self.cap = preset.cap;
+        self.k = preset.k;
+        self.vr = preset.vr;
+        self.vt = preset.vt;
+        self.vpeak = preset.vpeak;
+        self.a = preset.a;
+        self.b = preset.b;
+        self.c = preset.c;
+        self.d = preset.d;
+        self.vpeak_u = preset.vpeak_u;
+        self.c_u = preset.c_u;
+        self.nullcline = preset.nullcline;
+        self.set_initial_conditions(preset.vr, 0.0);
+    }
+
+    /// Stores the initial conditions. They also become the live state until
+    /// the first tick; afterwards they only take effect on a restart.
+    fn set_initial_conditions(&mut self, v0: f64, u0: f64) {
+        self.v0 = v0;
+        self.u0 = u0;
+        if self.last_tick.is_none() {
+            self.v = v0;
+            self.u = u0;
+            self.v_mv = v0;
+        }
+    }
+
+    fn restart(&mut self) {
+        self.v = self.v0;
+        self.u = self.u0;
+        self.v_mv = self.v;
+        self.spikes_this_tick = 0;
+        self.total_spikes = 0;
+        self.stepper.reset();
+    }
+
+    fn advance(&mut self, remaining_ms: f64) -> f64 {
+        let (cap, k, vr, vt) = (self.cap, self.k, self.vr, self.vt);
+        let (a, b, nullcline, i) = (self.a, self.b, self.nullcline, self.i_inj);
+        let f = move |v: f64, u: f64| {
+            let dv = (k * (v - vr) * (v - vt) - u + i) / cap;
+            let u_inf = match nullcline {
+                Nullcline::Linear => b * (v - vr),
+                Nullcline::Cubic { vb } if v >= vb => b * (v - vb).powi(3),
+                Nullcline::Cubic { .. } => 0.0,
+                Nullcline::Switched { vb, b_below } if v < vb => b_below * (v - vr),
+                Nullcline::Switched { .. } => b * (v - vr),
+            };
+            (dv, a * (u_inf - u))
+        };
+        self.stepper
+            .advance(f, &mut self.v, &mut self.u, remaining_ms)
+    }
+}
+
+impl PluginRuntime for Izhikevich2007Neuron {
+    fn set_config_value(&mut self, key: &str, value: &Value) {
+        if let Some(name) = value.as_str() {
+            match key {
+                "integrator" => {
+                    if let Some(integrator) = Integrator::from_name(name) {
+                        self.stepper.set_integrator(integrator);
+                    }
+                }
+                "preset" => {
+                    if let Some(preset) = presets::find(name) {
+                        self.apply_preset(preset);
+                    }
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        if let Some(v) = value.as_f64() {
+            match key {
+                "v" => self.set_initial_conditions(v, self.u0),
+                "u" => self.set_initial_conditions(self.v0, v),
+                "C" if v > 0.0 => self.cap = v,
+                "k" => self.k = v,
+                "vr" => self.vr = v,
+                "vt" => self.vt = v,
+                "vpeak" => self.vpeak = v,
+                "a" => self.a = v,
+                "b" => self.b = v,
+                "c" => self.c = v,
+                "d" => self.d = v,
+                "step_ms" => self.stepper.set_step_ms(v),
+                "tolerance" => self.stepper.set_tolerance(v),
+                _ => {}
+            }
+        }
+    }
+
+    fn set_input_value(&mut self, key: &str, v: f64) {
+        if key == "Injected current (pA)" {
+            self.i_inj = if v.is_finite() { v } else { 0.0 };
+        }
+    }
+
+    fn process_tick(&mut self, tick: u64, period_seconds: f64) {
+        if !period_seconds.is_finite() || period_seconds <= 0.0 {
+            return;
+        }
+
+        // A tick counter that goes backwards means the host restarted.
+        if self.last_tick.is_some_and(|last| tick < last) {
+            self.restart();
+        }
+        self.last_tick = Some(tick);
+
+        self.spikes_this_tick = 0;
+
+        let mut remaining_ms = period_seconds * 1000.0;
+
+        while remaining_ms > 0.0 {
+            remaining_ms -= self.advance(remaining_ms);
+
+            if self.v >= self.vpeak + self.vpeak_u * self.u || !self.v.is_finite() {
+                self.v = self.c + self.c_u * self.u;
+                self.u += self.d;
+                self.spikes_this_tick += 1;
+                self.total_spikes += 1;
+            }
+        }
+
+        self.v_mv = self.v;
+    }
+
+    fn get_output_value(&self, key: &str) -> f64 {
+        match key {
+            "Membrane potential (V)" => self.v_mv / 1000.0,
+            "Membrane potential (mV)" => self.v_mv,
+            "Recovery current (pA)" => self.u,
+            "Spike" => f64::from(u8::from(self.spikes_this_tick > 0)),
+            "Spikes this tick" => f64::from(self.spikes_this_tick),
+            "Total spikes" => self.total_spikes as f64,
+            _ => 0.0,
+        }
+    }
+
+    fn get_internal_value(&self, key: &str) -> Option<f64> {
+        match key {
+            "v" => Some(self.v),
+            "u" => Some(self.u),
+            _ => None,
+        }
+    }
+}
+
+rtsyn_plugin::export_plugin!(Izhikevich2007Neuron);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(neuron: &mut Izhikevich2007Neuron, ticks: std::ops::Range<u64>) -> Vec<u64> {
+        ticks
+            .filter(|&tick| {
+                neuron.process_tick(tick, 1e-4);
+                neuron.spikes_this_tick > 0
+            })
+            .collect()
+    }
+
+    fn preset_neuron(name: &str, i_pa: f64) -> Izhikevich2007Neuron {
+        let mut neuron = Izhikevich2007Neuron::default();
+        neuron.set_config_value("step_ms", &Value::from(0.1));
+        neuron.set_config_value("preset", &Value::from(name));
+        neuron.set_input_value("Injected current (pA)", i_pa);
+        neuron
+    }
+
+    #[test]
+    fn restart_repeats_the_first_run() {
+        let mut neuron = preset_neuron("rs", 100.0);
+        neuron.set_config_value("v", &Value::from(-70.0));
+        let first = run(&mut neuron, 0..5000);
+        assert!(!first.is_empty());
+        // Initial conditions edited while running wait for the restart.
+        neuron.set_config_value("v", &Value::from(-70.0));
+        assert_ne!(neuron.v, -70.0);
+        let second = run(&mut neuron, 0..5000);
+        assert_eq!(first, second);
+        assert_eq!(neuron.total_spikes, first.len() as u64);
+    }
+
+    #[test]
+    fn presets_set_their_cell_types() {
+        // 500 ms at 100 pA (FS, LTS: 200 pA): regular spiking adapts and
+        // fast spiking fires far more often.
+        let rs = run(&mut preset_neuron("rs", 100.0), 0..5000);
+        let fs = run(&mut preset_neuron("fs", 200.0), 0..5000);
+        assert!(!rs.is_empty() && fs.len() > 2 * rs.len(), "{rs:?} {fs:?}");
+        let isi: Vec<u64> = rs.windows(2).map(|w| w[1] - w[0]).collect();
+        assert!(isi[0] < isi[isi.len() - 1], "{rs:?}");
+        // Chattering cells fire bursts of closely spaced spikes.
+        let ch = run(&mut preset_neuron("ch", 200.0), 0..5000);
+        assert!(ch.windows(2).any(|w| w[1] - w[0] < 100), "{ch:?}");
+        assert!(presets::find("Thalamic relay").is_some());
+    }
+}
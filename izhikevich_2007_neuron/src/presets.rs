@@ -0,0 +1,173 @@
+use crate::Nullcline;
+
+/// Parameter set of a cell type from chapter 8 of Izhikevich (2007).
+#[derive(Debug, Clone, Copy)]
+pub struct Preset {
+    pub names: &'static [&'static str],
+    pub cap: f64,
+    pub k: f64,
+    pub vr: f64,
+    pub vt: f64,
+    pub vpeak: f64,
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub vpeak_u: f64,
+    pub c_u: f64,
+    pub nullcline: Nullcline,
+}
+
+#[allow(clippy::too_many_arguments)]
+const fn preset(
+    names: &'static [&'static str],
+    cap: f64,
+    k: f64,
+    vr: f64,
+    vt: f64,
+    vpeak: f64,
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+) -> Preset {
+    Preset {
+        names,
+        cap,
+        k,
+        vr,
+        vt,
+        vpeak,
+        a,
+        b,
+        c,
+        d,
+        vpeak_u: 0.0,
+        c_u: 0.0,
+        nullcline: Nullcline::Linear,
+    }
+}
+
+pub const CELL_TYPES: &[Preset] = &[
+    preset(
+        &["rs", "regular_spiking"],
+        100.0,
+        0.7,
+        -60.0,
+        -40.0,
+        35.0,
+        0.03,
+        -2.0,
+        -50.0,
+        100.0,
+    ),
+    preset(
+        &["ib", "intrinsically_bursting"],
+        150.0,
+        1.2,
+        -75.0,
+        -45.0,
+        50.0,
+        0.01,
+        5.0,
+        -56.0,
+        130.0,
+    ),
+    preset(
+        &["ch", "chattering"],
+        50.0,
+        1.5,
+        -60.0,
+        -40.0,
+        25.0,
+        0.03,
+        1.0,
+        -40.0,
+        150.0,
+    ),
+    Preset {
+        nullcline: Nullcline::Cubic { vb: -55.0 },
+        ..preset(
+            &["fs", "fast_spiking"],
+            20.0,
+            1.0,
+            -55.0,
+            -40.0,
+            25.0,
+            0.2,
+            0.025,
+            -45.0,
+            0.0,
+        )
+    },
+    // vpeak = 40 - 0.1 u, c = -53 + 0.04 u
+    Preset {
+        vpeak_u: -0.1,
+        c_u: 0.04,
+        ..preset(
+            &["lts", "low_threshold_spiking"],
+            100.0,
+            1.0,
+            -56.0,
+            -42.0,
+            40.0,
+            0.03,
+            8.0,
+            -53.0,
+            20.0,
+        )
+    },
+    // vpeak = 35 + 0.1 u, c = -60 - 0.1 u, b = 15 below -65 mV
+    Preset {
+        vpeak_u: 0.1,
+        c_u: -0.1,
+        nullcline: Nullcline::Switched {
+            vb: -65.0,
+            b_below: 15.0,
+        },
+        ..preset(
+            &["tc", "thalamocortical", "thalamic_relay"],
+            200.0,
+            1.6,
+            -60.0,
+            -50.0,
+            35.0,
+            0.01,
+            0.0,
+            -60.0,
+            10.0,
+        )
+    },
+    preset(
+        &["rtn", "reticular", "thalamic_reticular"],
+        40.0,
+        0.25,
+        -65.0,
+        -45.0,
+        0.0,
+        0.015,
+        10.0,
+        -55.0,
+        50.0,
+    ),
+    preset(
+        &["msn", "medium_spiny", "striatal_msn"],
+        50.0,
+        1.0,
+        -80.0,
+        -25.0,
+        40.0,
+        0.01,
+        -20.0,
+        -55.0,
+        150.0,
+    ),
+];
+
+/// Looks up a preset by any of its names, ignoring case, spaces and dashes.
+pub fn find(name: &str) -> Option<&'static Preset> {
+    let name = name.trim().to_ascii_lowercase().replace([' ', '-'], "_");
+    CELL_TYPES
+        .iter()
+        .find(|preset| preset.names.contains(&name.as_str()))
+}
@@ -0,0 +1,461 @@
+use izhikevich_2003_neuron_rust::integrator::{Integrator, Stepper, DEFAULT_STEP_MS};
+use izhikevich_2003_neuron_rust::model::Equations;
+use izhikevich_2003_neuron_rust::presets::{self, Preset};
+use izhikevich_2003_neuron_rust::stimulus::Protocol;
+use rtsyn_plugin::prelude::*;
+use serde_json::Value;
+
+const DEFAULT_N: usize = 100;
+const MAX_N: usize = 100_000;
+
+/// `n` uncoupled Izhikevich (2003) neurons stored as struct-of-arrays.
+#[derive(Debug)]
+struct IzhikevichPopulation {
+    i_syn: f64,
+    v: Vec<f64>,
+    u: Vec<f64>,
+    a: Vec<f64>,
+    b: Vec<f64>,
+    c: Vec<f64>,
+    d: Vec<f64>,
+    i_bias: Vec<f64>,
+    // Initial conditions, returned to on a restart.
+    v0: Vec<f64>,
+    u0: Vec<f64>,
+    equations: Vec<Equations>,
+    steppers: Vec<Stepper>,
+    protocols: Vec<Option<&'static Protocol>>,
+    protocol_t_ms: Vec<f64>,
+    v_peak: f64,
+    // Values used for neurons added when `n` grows.
+    template: Preset,
+    template_i_bias: f64,
+    fired: Vec<usize>,
+    spikes_this_tick: u64,
+    total_spikes: u64,
+    mean_v: f64,
+    last_tick: Option<u64>,
+}
+
+impl Default for IzhikevichPopulation {
+    fn default() -> Self {
+        let mut population = Self {
+            i_syn: 0.0,
+            v: Vec::new(),
+            u: Vec::new(),
+            a: Vec::new(),
+            b: Vec::new(),
+            c: Vec::new(),
+            d: Vec::new(),
+            i_bias: Vec::new(),
+            v0: Vec::new(),
+            u0: Vec::new(),
+            equations: Vec::new(),
+            steppers: Vec::new(),
+            protocols: Vec::new(),
+            protocol_t_ms: Vec::new(),
+            v_peak: 30.0,
+            template: presets::NEURON_CLASSES[0],
+            template_i_bias: 0.0,
+            fired: Vec::new(),
+            spikes_this_tick: 0,
+            total_spikes: 0,
+            mean_v: -65.0,
+            last_tick: None,
+        };
+        population.resize(DEFAULT_N);
+        population
+    }
+}
+
+impl PluginDescriptor for IzhikevichPopulation {
+    fn name() -> &'static str {
+        "Izhikevich Population"
+    }
+
+    fn kind() -> &'static str {
+        "izhikevich_population"
+    }
+
+    fn plugin_type() -> PluginType {
+        PluginType::Computational
+    }
+
+    fn inputs() -> &'static [&'static str] {
+        &["i_syn"]
+    }
+
+    fn outputs() -> &'static [&'static str] {
+        &[
+            "Population spike count",
+            "Mean membrane potential (mV)",
+            "Total spikes",
+            "Neurons fired",
+            // Raster of the first eight neurons to fire; `Neurons fired` has the full count.
+            "Fired neuron 1",
+            "Fired neuron 2",
+            "Fired neuron 3",
+            "Fired neuron 4",
+            "Fired neuron 5",
+            "Fired neuron 6",
+            "Fired neuron 7",
+            "Fired neuron 8",
+        ]
+    }
+
+    fn internal_variables() -> &'static [&'static str] {
+        &["n", "mean_v", "mean_u"]
+    }
+
+    fn default_vars() -> Vec<(&'static str, Value)> {
+        vec![
+            ("n", DEFAULT_N.into()),
+            ("preset", "custom".into()),
+            ("a", 0.02.into()),
+            ("b", 0.2.into()),
+            ("c", (-65.0).into()),
+            ("d", 8.0.into()),
+            ("i_bias", 0.0.into()),
+            ("v_peak", 30.0.into()),
+            ("integrator", "euler".into()),
+            ("step_ms", DEFAULT_STEP_MS.into()),
+            ("tolerance", 1e-3.into()),
+        ]
+    }
+
+    fn behavior() -> PluginBehavior {
+        PluginBehavior {
+            supports_start_stop: true,
+            supports_restart: true,
+            supports_apply: false,
+            extendable_inputs: ExtendableInputs::None,
+            loads_started: false,
+            external_window: false,
+            starts_expanded: true,
+            start_requires_connected_inputs: Vec::new(),
+            start_requires_connected_outputs: Vec::new(),
+        }
+    }
+}
+
+impl IzhikevichPopulation {
+    fn len(&self) -> usize {
+        self.v.len()
+    }
+
+    /// Grows or shrinks the population; new neurons take the template values.
+    fn resize(&mut self, n: usize) {
+        let t = self.template;
+        let stepper = self.steppers.first().copied().unwrap_or_default();
+        self.v.resize(n, t.v0);
+        self.u.resize(n, t.u0());
+        self.a.resize(n, t.a);
+        self.b.resize(n, t.b);
+        self.c.resize(n, t.c);
+        self.d.resize(n, t.d);
+        self.i_bias.resize(n, self.template_i_bias);
+        self.v0.resize(n, t.v0);
+        self.u0.resize(n, t.u0());
+        self.equations.resize(n, t.equations);
+        self.steppers.resize(n, stepper);
+        self.protocols.resize(n, t.protocol);
+        self.protocol_t_ms.resize(n, 0.0);
+        // Reserved here so `process_tick` never allocates.
+        self.fired = Vec::with_capacity(n);
+    }
+
+    fn apply_preset(&mut self, k: usize, preset: &Preset) {
+        self.a[k] = preset.a;
+        self.b[k] = preset.b;
+        self.c[k] = preset.c;
+        self.d[k] = preset.d;
+        self.v0[k] = preset.v0;
+        self.u0[k] = preset.u0();
+        if self.last_tick.is_none() {
+            self.v[k] = preset.v0;
+            self.u[k] = preset.u0();
+        }
+        self.equations[k] = preset.equations;
+        self.protocols[k] = preset.protocol;
+        self.protocol_t_ms[k] = 0.0;
+        if let Some(integrator) = preset.integrator {
+            self.steppers[k].set_integrator(integrator);
+        }
+        if let Some(step_ms) = preset.step_ms {
+            self.steppers[k].set_step_ms(step_ms);
+        }
+    }
+
+    /// Sets the initial `v` (or `u`) from a number or an array; before the
+    /// first tick the live state follows.
+    fn set_initial(&mut self, key: &str, value: &Value) {
+        let (initial, live) = match key {
+            "v" => {
+                self.template.v0 = value.as_f64().unwrap_or(self.template.v0);
+                (&mut self.v0, &mut self.v)
+            }
+            _ => {
+                self.template.u0 = value.as_f64().or(self.template.u0);
+                (&mut self.u0, &mut self.u)
+            }
+        };
+        Self::set_per_neuron(initial, value);
+        if self.last_tick.is_none() {
+            live.copy_from_slice(initial);
+        }
+    }
+
+    /// Returns every neuron to its initial conditions, with no spikes and
+    /// stimulus protocols from their start.
+    fn restart(&mut self) {
+        self.v.copy_from_slice(&self.v0);
+        self.u.copy_from_slice(&self.u0);
+        self.protocol_t_ms.fill(0.0);
+        self.for_each_stepper(Stepper::reset);
+        self.fired.clear();
+        self.spikes_this_tick = 0;
+        self.total_spikes = 0;
+        self.mean_v = self.v.iter().sum::<f64>() / self.len() as f64;
+    }
+
+    /// Sets a per-neuron parameter from a number (all neurons) or an array
+    /// (element-wise, extra elements ignored).
+    fn set_per_neuron(values: &mut [f64], value: &Value) {
+        if let Some(x) = value.as_f64() {
+            values.fill(x);
+        } else if let Some(items) = value.as_array() {
+            for (slot, item) in values.iter_mut().zip(items) {
+                if let Some(x) = item.as_f64() {
+                    *slot = x;
+                }
+            }
+        }
+    }
+
+    fn set_preset(&mut self, value: &Value) {
+        if let Some(preset) = value.as_str().and_then(presets::find) {
+            self.template = *preset;
+            for k in 0..self.len() {
+                self.apply_preset(k, preset);
+            }
+        } else if let Some(items) = value.as_array() {
+            for (k, item) in items.iter().enumerate().take(self.len()) {
+                if let Some(preset) = item.as_str().and_then(presets::find) {
+                    self.apply_preset(k, preset);
+                }
+            }
+        }
+    }
+
+    fn for_each_stepper(&mut self, f: impl Fn(&mut Stepper)) {
+        self.steppers.iter_mut().for_each(f);
+    }
+}
+
+impl PluginRuntime for IzhikevichPopulation {
+    fn set_config_value(&mut self, key: &str, value: &Value) {
+        match key {
+            "n" => {
+                if let Some(n) = value.as_f64().filter(|n| n.is_finite()) {
+                    self.resize((n.round() as usize).clamp(1, MAX_N));
+                }
+            }
+            "preset" => self.set_preset(value),
+            "a" => {
+                self.template.a = value.as_f64().unwrap_or(self.template.a);
+                Self::set_per_neuron(&mut self.a, value);
+            }
+            "b" => {
+                self.template.b = value.as_f64().unwrap_or(self.template.b);
+                Self::set_per_neuron(&mut self.b, value);
+            }
+            "c" => {
+                self.template.c = value.as_f64().unwrap_or(self.template.c);
+                Self::set_per_neuron(&mut self.c, value);
+            }
+            "d" => {
+                self.template.d = value.as_f64().unwrap_or(self.template.d);
+                Self::set_per_neuron(&mut self.d, value);
+            }
+            "i_bias" => {
+                self.template_i_bias = value.as_f64().unwrap_or(self.template_i_bias);
+                Self::set_per_neuron(&mut self.i_bias, value);
+            }
+            "v" | "u" => self.set_initial(key, value),
+            "v_peak" => self.v_peak = value.as_f64().unwrap_or(self.v_peak),
+            "integrator" => {
+                if let Some(integrator) = value.as_str().and_then(Integrator::from_name) {
+                    self.for_each_stepper(|s| s.set_integrator(integrator));
+                }
+            }
+            "step_ms" => {
+                if let Some(step_ms) = value.as_f64() {
+                    self.for_each_stepper(|s| s.set_step_ms(step_ms));
+                }
+            }
+            "tolerance" => {
+                if let Some(tolerance) = value.as_f64() {
+                    self.for_each_stepper(|s| s.set_tolerance(tolerance));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn set_input_value(&mut self, key: &str, v: f64) {
+        if key == "i_syn" {
+            self.i_syn = if v.is_finite() { v } else { 0.0 };
+        }
+    }
+
+    fn process_tick(&mut self, tick: u64, period_seconds: f64) {
+        if !period_seconds.is_finite() || period_seconds <= 0.0 {
+            return;
+        }
+        // The host restarts its tick count on a restart.
+        if self.last_tick.is_some_and(|last| tick < last) {
+            self.restart();
+        }
+        self.last_tick = Some(tick);
+
+        self.fired.clear();
+        self.spikes_this_tick = 0;
+        let period_ms = period_seconds * 1000.0;
+        let mut sum_v = 0.0;
+
+        for k in 0..self.len() {
+            let mut remaining_ms = period_ms;
+            let mut spiked = false;
+
+            while remaining_ms > 0.0 {
+                let protocol = self.protocols[k];
+                let i = self.i_syn
+                    + self.i_bias[k]
+                    + protocol.map_or(0.0, |p| p.current(self.protocol_t_ms[k]));
+                let f = self.equations[k].rhs(self.a[k], self.b[k], i);
+                let dt_ms =
+                    self.steppers[k].advance(f, &mut self.v[k], &mut self.u[k], remaining_ms);
+                remaining_ms -= dt_ms;
+                if protocol.is_some() {
+                    self.protocol_t_ms[k] += dt_ms;
+                }
+
+                if self.v[k] >= self.v_peak || !self.v[k].is_finite() {
+                    self.v[k] = self.c[k];
+                    self.u[k] += self.d[k];
+                    self.spikes_this_tick += 1;
+                    spiked = true;
+                }
+            }
+
+            if spiked {
+                self.fired.push(k);
+            }
+            sum_v += self.v[k];
+        }
+
+        self.total_spikes += self.spikes_this_tick;
+        self.mean_v = sum_v / self.len() as f64;
+    }
+
+    fn get_output_value(&self, key: &str) -> f64 {
+        match key {
+            "Population spike count" => self.spikes_this_tick as f64,
+            "Mean membrane potential (mV)" => self.mean_v,
+            "Total spikes" => self.total_spikes as f64,
+            "Neurons fired" => self.fired.len() as f64,
+            // "Fired neuron <j>": index of the j-th neuron that fired this tick, or -1.
+            _ => key
+                .strip_prefix("Fired neuron ")
+                .and_then(|j| j.parse::<usize>().ok())
+                .and_then(|j| self.fired.get(j.checked_sub(1)?))
+                .map_or(-1.0, |&k| k as f64),
+        }
+    }
+
+    fn get_internal_value(&self, key: &str) -> Option<f64> {
+        match key {
+            "n" => Some(self.len() as f64),
+            "mean_v" => Some(self.mean_v),
+            "mean_u" => Some(self.u.iter().sum::<f64>() / self.len() as f64),
+            _ => None,
+        }
+    }
+}
+
+rtsyn_plugin::export_plugin!(IzhikevichPopulation);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn population(n: usize, preset: Value) -> IzhikevichPopulation {
+        let mut population = IzhikevichPopulation::default();
+        population.set_config_value("n", &Value::from(n));
+        population.set_config_value("preset", &preset);
+        population
+    }
+
+    /// Spike ticks of each neuron, read back from the raster outputs.
+    fn raster(population: &mut IzhikevichPopulation, ticks: u64, period_s: f64) -> Vec<Vec<u64>> {
+        let mut spikes = vec![Vec::new(); population.len()];
+        for tick in 0..ticks {
+            population.process_tick(tick, period_s);
+            for j in 1..=8 {
+                let k = population.get_output_value(&format!("Fired neuron {j}"));
+                if k >= 0.0 {
+                    spikes[k as usize].push(tick);
+                }
+            }
+        }
+        spikes
+    }
+
+    #[test]
+    fn features_replay_their_protocols() {
+        // 400 ms at the published 0.25 ms step, with no input at all.
+        let mut population =
+            population(2, Value::from(vec!["a_tonic_spiking", "b_phasic_spiking"]));
+        assert_eq!(
+            population.steppers[0].to_json()["integrator"],
+            "semi_implicit_euler"
+        );
+        let spikes = raster(&mut population, 1600, 0.25e-3);
+        assert!(spikes[0].len() >= 10 && spikes[0][0] > 40, "{spikes:?}");
+        assert_eq!(spikes[1].len(), 1);
+    }
+
+    #[test]
+    fn spike_count_counts_every_spike() {
+        let mut population = population(1, Value::from("fs"));
+        population.set_config_value("i_bias", &Value::from(10.0));
+        population.process_tick(0, 0.1);
+        assert_eq!(population.get_output_value("Neurons fired"), 1.0);
+        let spikes = population.get_output_value("Population spike count");
+        assert!(spikes > 5.0, "{spikes}");
+        assert_eq!(population.get_output_value("Total spikes"), spikes);
+        assert_eq!(population.get_output_value("Fired neuron 2"), -1.0);
+    }
+
+    #[test]
+    fn restart_repeats_the_first_run() {
+        let mut population = population(1, Value::from("rs"));
+        population.set_config_value("v", &Value::from(-70.0));
+        population.set_config_value("n", &Value::from(3));
+        assert_eq!(population.v, [-70.0; 3]);
+        population.set_config_value("i_bias", &Value::from(vec![5.0, 10.0, 15.0]));
+
+        let first = raster(&mut population, 2000, 1e-4);
+        assert!(first.iter().all(|spikes| !spikes.is_empty()));
+        // Initial conditions edited while running wait for the restart.
+        population.set_config_value("v", &Value::from(-60.0));
+        assert!(population.v.iter().all(|&v| v != -60.0));
+        let second = raster(&mut population, 2000, 1e-4);
+        assert_ne!(first, second);
+
+        population.set_config_value("v", &Value::from(-70.0));
+        assert_eq!(raster(&mut population, 2000, 1e-4), first);
+        let total: usize = first.iter().map(Vec::len).sum();
+        assert_eq!(population.get_output_value("Total spikes"), total as f64);
+    }
+}
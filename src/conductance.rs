@@ -0,0 +1,229 @@
+use serde_json::{json, Value};
+
+use crate::snapshot::read;
+
+/// How the `g_<receptor>` ports drive the conductances.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConductanceInput {
+    /// The port value is the conductance itself (dynamic clamp).
+    Clamp,
+    /// The port value is added to `g` at every tick and `g` decays with its
+    /// time constant, so spike pulses act as increments.
+    Increment,
+}
+
+impl ConductanceInput {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "clamp" | "dynamic_clamp" | "set" => Some(Self::Clamp),
+            "increment" | "add" | "pulse" => Some(Self::Increment),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Clamp => "clamp",
+            Self::Increment => "increment",
+        }
+    }
+}
+
+/// One receptor type: `g (E_rev - v)`, with `g` decaying as `g' = -g / tau`.
+#[derive(Debug, Clone, Copy)]
+pub struct Conductance {
+    pub g: f64,
+    pub e_rev: f64,
+    /// Decay time constant in ms, used in [`ConductanceInput::Increment`]
+    /// mode. Non-positive values disable the kinetics and take the port value
+    /// as the conductance itself.
+    pub tau_ms: f64,
+    pub input: f64,
+}
+
+impl Conductance {
+    const fn new(e_rev: f64, tau_ms: f64) -> Self {
+        Self {
+            g: 0.0,
+            e_rev,
+            tau_ms,
+            input: 0.0,
+        }
+    }
+
+    fn has_kinetics(&self, mode: ConductanceInput) -> bool {
+        mode == ConductanceInput::Increment && self.tau_ms > 0.0
+    }
+
+    /// Applies the port value at the start of a tick: an increment when the
+    /// kinetics are enabled, the conductance itself otherwise.
+    fn latch_input(&mut self, mode: ConductanceInput) {
+        if self.has_kinetics(mode) {
+            self.g += self.input;
+        } else {
+            self.g = self.input;
+        }
+    }
+
+    fn decay(&mut self, mode: ConductanceInput, dt_ms: f64) {
+        if self.has_kinetics(mode) {
+            self.g *= (-dt_ms / self.tau_ms).exp();
+        }
+    }
+
+    fn to_json(self) -> Value {
+        json!({ "g": self.g, "e_rev": self.e_rev, "tau_ms": self.tau_ms })
+    }
+
+    fn restore(&mut self, value: &Value) {
+        read(value, "g", &mut self.g);
+        read(value, "e_rev", &mut self.e_rev);
+        read(value, "tau_ms", &mut self.tau_ms);
+    }
+}
+
+/// AMPA, NMDA, GABA_A and GABA_B conductances of Izhikevich & Edelman (2008).
+#[derive(Debug, Clone, Copy)]
+pub struct Conductances {
+    pub ampa: Conductance,
+    pub nmda: Conductance,
+    pub gaba_a: Conductance,
+    pub gaba_b: Conductance,
+    pub input_mode: ConductanceInput,
+}
+
+impl Default for Conductances {
+    fn default() -> Self {
+        Self {
+            ampa: Conductance::new(0.0, 5.0),
+            nmda: Conductance::new(0.0, 150.0),
+            gaba_a: Conductance::new(-70.0, 6.0),
+            gaba_b: Conductance::new(-90.0, 150.0),
+            input_mode: ConductanceInput::Clamp,
+        }
+    }
+}
+
+impl Conductances {
+    /// Receptor by its port suffix, e.g. `GABA_A` for `g_GABA_A`.
+    pub fn get_mut(&mut self, receptor: &str) -> Option<&mut Conductance> {
+        match receptor.to_ascii_lowercase().as_str() {
+            "ampa" => Some(&mut self.ampa),
+            "nmda" => Some(&mut self.nmda),
+            "gaba_a" => Some(&mut self.gaba_a),
+            "gaba_b" => Some(&mut self.gaba_b),
+            _ => None,
+        }
+    }
+
+    /// Synaptic current at membrane potential `v`, with the Mg2+ block of
+    /// the NMDA receptor.
+    pub fn current(&self, v: f64) -> f64 {
+        let x = ((v + 80.0) / 60.0).powi(2);
+        let mg_block = x / (1.0 + x);
+        self.ampa.g * (self.ampa.e_rev - v)
+            + self.nmda.g * mg_block * (self.nmda.e_rev - v)
+            + self.gaba_a.g * (self.gaba_a.e_rev - v)
+            + self.gaba_b.g * (self.gaba_b.e_rev - v)
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "ampa": self.ampa.to_json(),
+            "nmda": self.nmda.to_json(),
+            "gaba_a": self.gaba_a.to_json(),
+            "gaba_b": self.gaba_b.to_json(),
+            "input_mode": self.input_mode.name(),
+        })
+    }
+
+    pub fn restore(&mut self, value: &Value) {
+        for receptor in ["ampa", "nmda", "gaba_a", "gaba_b"] {
+            if let (Some(saved), Some(c)) = (value.get(receptor), self.get_mut(receptor)) {
+                c.restore(saved);
+            }
+        }
+        if let Some(mode) = value
+            .get("input_mode")
+            .and_then(Value::as_str)
+            .and_then(ConductanceInput::from_name)
+        {
+            self.input_mode = mode;
+        }
+    }
+
+    pub fn latch_inputs(&mut self) {
+        let mode = self.input_mode;
+        self.for_each(|c| c.latch_input(mode));
+    }
+
+    pub fn decay(&mut self, dt_ms: f64) {
+        let mode = self.input_mode;
+        self.for_each(|c| c.decay(mode, dt_ms));
+    }
+
+    pub fn reset(&mut self) {
+        self.for_each(|c| c.g = 0.0);
+    }
+
+    fn for_each(&mut self, f: impl Fn(&mut Conductance)) {
+        for c in [
+            &mut self.ampa,
+            &mut self.nmda,
+            &mut self.gaba_a,
+            &mut self.gaba_b,
+        ] {
+            f(c);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamp_holds_the_port_value() {
+        let mut conductances = Conductances::default();
+        conductances.ampa.input = 0.5;
+        for _ in 0..3 {
+            conductances.latch_inputs();
+            conductances.decay(1.0);
+            assert_eq!(conductances.ampa.g, 0.5);
+        }
+        conductances.ampa.input = 0.0;
+        conductances.latch_inputs();
+        assert_eq!(conductances.ampa.g, 0.0);
+    }
+
+    #[test]
+    fn increments_add_up_and_decay() {
+        let mut conductances = Conductances {
+            input_mode: ConductanceInput::Increment,
+            ..Conductances::default()
+        };
+        conductances.gaba_a.input = 1.0;
+        conductances.latch_inputs();
+        conductances.latch_inputs();
+        assert_eq!(conductances.gaba_a.g, 2.0);
+        conductances.decay(6.0);
+        assert!((conductances.gaba_a.g - 2.0 / std::f64::consts::E).abs() < 1e-12);
+        // Without kinetics the port is the conductance, whatever the mode.
+        conductances.gaba_a.tau_ms = 0.0;
+        conductances.latch_inputs();
+        assert_eq!(conductances.gaba_a.g, 1.0);
+    }
+
+    #[test]
+    fn currents_drive_v_towards_the_reversal_potentials() {
+        let mut conductances = Conductances::default();
+        conductances.gaba_b.g = 1.0;
+        assert_eq!(conductances.current(-90.0), 0.0);
+        assert!(conductances.current(-65.0) < 0.0);
+        // The Mg2+ block shuts NMDA off at hyperpolarised potentials.
+        conductances.gaba_b.g = 0.0;
+        conductances.nmda.g = 1.0;
+        let block = |v: f64| conductances.current(v) / -v;
+        assert!(block(-80.0) == 0.0 && block(-60.0) < 0.1 && block(-20.0) > 0.4);
+    }
+}
@@ -0,0 +1,218 @@
+use serde_json::{json, Value};
+
+use crate::snapshot::read;
+use crate::synapse::MAX_INPUTS;
+
+/// Period assumed until the host's is known: buffers sized for it hold the
+/// configured delays at rates up to 20 kHz.
+const DEFAULT_PERIOD_MS: f64 = 0.05;
+
+/// Conduction delay of one input: a ring buffer holding the port values of
+/// the last ticks. It is only resized from `set_config_value`, so
+/// `process_tick` never allocates.
+#[derive(Debug, Clone)]
+pub struct DelayLine {
+    /// Latest port value, not yet delayed.
+    pub input: f64,
+    delay_ms: f64,
+    buffer: Vec<f64>,
+    head: usize,
+}
+
+impl Default for DelayLine {
+    fn default() -> Self {
+        Self {
+            input: 0.0,
+            delay_ms: 0.0,
+            buffer: vec![0.0],
+            head: 0,
+        }
+    }
+}
+
+impl DelayLine {
+    fn ticks(&self, period_ms: f64) -> usize {
+        (self.delay_ms / period_ms).round() as usize
+    }
+
+    /// Sets the delay and sizes the buffer for it at the given tick period.
+    pub fn set_delay(&mut self, delay_ms: f64, period_ms: f64) {
+        if !delay_ms.is_finite() || delay_ms < 0.0 {
+            return;
+        }
+        self.delay_ms = delay_ms;
+        let len = self.ticks(period_ms) + 1;
+        if len != self.buffer.len() {
+            self.buffer = vec![0.0; len];
+            self.head = 0;
+        }
+    }
+
+    /// Pushes this tick's input and returns the one from `delay_ms` ago. If
+    /// the host ticks faster than the buffer was sized for, the delay is
+    /// capped at the buffer length until it is set again.
+    pub fn shift(&mut self, period_ms: f64) -> f64 {
+        let len = self.buffer.len();
+        let ticks = self.ticks(period_ms).min(len - 1);
+        self.buffer[self.head] = self.input;
+        let out = self.buffer[(self.head + len - ticks) % len];
+        self.head = (self.head + 1) % len;
+        out
+    }
+
+    /// Drops the spikes in flight.
+    pub fn clear(&mut self) {
+        self.buffer.fill(0.0);
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({ "delay_ms": self.delay_ms, "buffer": self.buffer, "head": self.head })
+    }
+
+    pub fn restore(&mut self, value: &Value, period_ms: f64) {
+        let mut delay_ms = self.delay_ms;
+        read(value, "delay_ms", &mut delay_ms);
+        self.set_delay(delay_ms, period_ms);
+        let saved = value.get("buffer").and_then(Value::as_array);
+        let head = value.get("head").and_then(Value::as_u64);
+        if let (Some(saved), Some(head)) = (saved, head) {
+            if saved.len() == self.buffer.len() && (head as usize) < saved.len() {
+                for (slot, x) in self.buffer.iter_mut().zip(saved) {
+                    *slot = x.as_f64().unwrap_or(0.0);
+                }
+                self.head = head as usize;
+            }
+        }
+    }
+}
+
+/// Per-input delay lines and the tick period their buffers are sized for.
+#[derive(Debug, Clone)]
+pub struct Delays {
+    pub lines: [DelayLine; MAX_INPUTS],
+    period_ms: f64,
+}
+
+impl Default for Delays {
+    fn default() -> Self {
+        Self {
+            lines: Default::default(),
+            period_ms: DEFAULT_PERIOD_MS,
+        }
+    }
+}
+
+impl Delays {
+    /// Records the host's period; buffers follow it the next time the delays
+    /// are set.
+    pub fn observe_period(&mut self, period_ms: f64) {
+        self.period_ms = period_ms;
+    }
+
+    /// Handles `input_delays_ms`: an array sets the inputs in order, a number
+    /// sets all of them.
+    pub fn set_config(&mut self, value: &Value) {
+        let period_ms = self.period_ms;
+        if let Some(items) = value.as_array() {
+            for (line, item) in self.lines.iter_mut().zip(items) {
+                if let Some(x) = item.as_f64() {
+                    line.set_delay(x, period_ms);
+                }
+            }
+        } else if let Some(x) = value.as_f64() {
+            self.lines
+                .iter_mut()
+                .for_each(|line| line.set_delay(x, period_ms));
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.iter_mut().for_each(DelayLine::clear);
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "period_ms": self.period_ms,
+            "lines": self.lines.iter().map(DelayLine::to_json).collect::<Vec<_>>(),
+        })
+    }
+
+    pub fn restore(&mut self, value: &Value) {
+        read(value, "period_ms", &mut self.period_ms);
+        if let Some(saved) = value.get("lines").and_then(Value::as_array) {
+            for (line, saved) in self.lines.iter_mut().zip(saved) {
+                line.restore(saved, self.period_ms);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Outputs of a line fed a single pulse on the first of `n` ticks.
+    fn pulse_response(line: &mut DelayLine, n: usize, period_ms: f64) -> Vec<f64> {
+        (0..n)
+            .map(|k| {
+                line.input = if k == 0 { 1.0 } else { 0.0 };
+                line.shift(period_ms)
+            })
+            .collect()
+    }
+
+    fn arrival(outputs: &[f64]) -> Option<usize> {
+        outputs.iter().position(|&x| x == 1.0)
+    }
+
+    #[test]
+    fn pulses_arrive_after_the_delay_rounded_to_ticks() {
+        let mut line = DelayLine::default();
+        assert_eq!(arrival(&pulse_response(&mut line, 5, 0.1)), Some(0));
+        line.set_delay(1.04, 0.1);
+        let outputs = pulse_response(&mut line, 30, 0.1);
+        assert_eq!(arrival(&outputs), Some(10));
+        assert_eq!(outputs.iter().sum::<f64>(), 1.0);
+    }
+
+    #[test]
+    fn faster_hosts_cap_the_delay_until_it_is_set_again() {
+        let mut line = DelayLine::default();
+        line.set_delay(2.0, 1.0);
+        assert_eq!(arrival(&pulse_response(&mut line, 10, 0.5)), Some(2));
+        line.set_delay(2.0, 0.5);
+        assert_eq!(arrival(&pulse_response(&mut line, 10, 0.5)), Some(4));
+        // Invalid delays are ignored.
+        line.set_delay(-1.0, 0.5);
+        assert_eq!(arrival(&pulse_response(&mut line, 10, 0.5)), Some(4));
+    }
+
+    /// Outputs of a line over the next 10 ticks with no further input.
+    fn drain(line: &mut DelayLine, period_ms: f64) -> Vec<f64> {
+        line.input = 0.0;
+        (0..10).map(|_| line.shift(period_ms)).collect()
+    }
+
+    #[test]
+    fn spikes_in_flight_survive_snapshots_but_not_clear() {
+        let mut delays = Delays::default();
+        delays.observe_period(0.5);
+        delays.set_config(&serde_json::json!([1.0, 3.0]));
+        let send = |delays: &mut Delays| {
+            delays.lines[1].input = 1.0;
+            delays.lines[1].shift(0.5);
+        };
+
+        send(&mut delays);
+        let mut restored = Delays::default();
+        restored.restore(&delays.to_json());
+        let in_flight = drain(&mut delays.lines[1], 0.5);
+        // Sent one tick ago with a six-tick delay.
+        assert_eq!(arrival(&in_flight), Some(5));
+        assert_eq!(drain(&mut restored.lines[1], 0.5), in_flight);
+
+        send(&mut delays);
+        delays.clear();
+        assert_eq!(arrival(&drain(&mut delays.lines[1], 0.5)), None);
+    }
+}
@@ -0,0 +1,103 @@
+/// How spikes are rendered in the membrane-potential outputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpikeDisplay {
+    /// Latch `v` as integrated; the reset usually hides the peak.
+    Raw,
+    /// Hold the peak value for the spiking tick, or for `spike_hold_ms`.
+    Peak,
+    /// Overlay a stereotyped action potential on `v` after each spike.
+    Waveform,
+}
+
+impl SpikeDisplay {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "raw" | "none" => Some(Self::Raw),
+            "peak" | "hold" => Some(Self::Peak),
+            "waveform" | "template" => Some(Self::Waveform),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Raw => "raw",
+            Self::Peak => "peak",
+            Self::Waveform => "waveform",
+        }
+    }
+}
+
+/// Stereotyped AP: decays from the peak back to `v` over `width_ms`.
+pub fn ap_template(v: f64, v_peak: f64, since_ms: f64, width_ms: f64) -> f64 {
+    let x = 4.0 * since_ms / width_ms;
+    v + (v_peak - v) * (-x * x).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use rtsyn_plugin::prelude::*;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::Izhikevich2003Neuron;
+
+    #[test]
+    fn template_decays_from_the_peak_to_v() {
+        assert_eq!(ap_template(-60.0, 30.0, 0.0, 1.5), 30.0);
+        let values: Vec<f64> = (0..=15)
+            .map(|k| ap_template(-60.0, 30.0, k as f64 * 0.1, 1.5))
+            .collect();
+        assert!(values.windows(2).all(|w| w[1] < w[0]));
+        assert!((values[15] + 60.0).abs() < 0.01);
+    }
+
+    /// Membrane output (mV) of a driven neuron on every 0.1 ms tick, and
+    /// whether it spiked.
+    fn trace(mode: &str, hold_ms: f64) -> Vec<(f64, bool)> {
+        let mut neuron = Izhikevich2003Neuron::default();
+        neuron.set_config_value("spike_display", &Value::from(mode));
+        neuron.set_config_value("spike_hold_ms", &Value::from(hold_ms));
+        neuron.set_input_value("i_syn", 10.0);
+        (0..1000)
+            .map(|tick| {
+                neuron.process_tick(tick, 1e-4);
+                (
+                    neuron.get_output_value("Membrane potential (mV)"),
+                    neuron.get_output_value("Spike") > 0.0,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn modes_show_the_spikes_the_reset_hides() {
+        let raw = trace("raw", 0.0);
+        let spike = raw.iter().position(|s| s.1).unwrap();
+        assert!(raw[spike].0 < 0.0);
+
+        let peak = trace("peak", 0.0);
+        assert_eq!(peak[spike].0, 30.0);
+        assert!(peak[spike + 1].0 < 0.0);
+        let held = trace("peak", 0.35);
+        // Spike times fall inside a tick, so the hold ends 3 or 4 ticks later.
+        assert!(held[spike..spike + 3].iter().all(|s| s.0 == 30.0));
+        assert!(held[spike + 4..spike + 10].iter().all(|s| s.0 < 0.0));
+
+        // The template starts near the peak on the spiking tick and then decays.
+        let waveform = trace("waveform", 0.0);
+        assert!(waveform[spike].0 > 20.0);
+        assert!(waveform[spike + 5].0 < waveform[spike + 1].0);
+        assert!(waveform[spike + 20].0 == raw[spike + 20].0);
+    }
+
+    #[test]
+    fn names_are_parsed_loosely() {
+        assert_eq!(SpikeDisplay::from_name(" Hold "), Some(SpikeDisplay::Peak));
+        assert_eq!(
+            SpikeDisplay::from_name("template"),
+            Some(SpikeDisplay::Waveform)
+        );
+        assert_eq!(SpikeDisplay::from_name("spiky"), None);
+    }
+}
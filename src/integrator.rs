@@ -0,0 +1,309 @@
+use serde_json::{json, Value};
+
+use crate::snapshot::read;
+
+pub const DEFAULT_STEP_MS: f64 = 0.5;
+pub const MIN_STEP_MS: f64 = 1e-4;
+
+/// Numerical scheme used to advance `(v, u)` inside `process_tick`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Integrator {
+    Euler,
+    /// Two half-steps for `v`, then one full step for `u` with the updated `v`,
+    /// as in the MATLAB code of Izhikevich (2003).
+    SplitEuler,
+    /// One full step for `v`, then one for `u` with the updated `v`, as in
+    /// the MATLAB code of Izhikevich (2004).
+    SemiImplicitEuler,
+    Heun,
+    Rk4,
+    /// Runge-Kutta-Fehlberg 4(5) with adaptive step size.
+    Rk45,
+}
+
+impl Integrator {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "euler" => Some(Self::Euler),
+            "split_euler" | "half_step" | "izhikevich" => Some(Self::SplitEuler),
+            "semi_implicit_euler" | "symplectic_euler" => Some(Self::SemiImplicitEuler),
+            "heun" | "rk2" => Some(Self::Heun),
+            "rk4" => Some(Self::Rk4),
+            "rk45" | "rkf45" | "adaptive" => Some(Self::Rk45),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Euler => "euler",
+            Self::SplitEuler => "split_euler",
+            Self::SemiImplicitEuler => "semi_implicit_euler",
+            Self::Heun => "heun",
+            Self::Rk4 => "rk4",
+            Self::Rk45 => "rk45",
+        }
+    }
+
+    /// Advances the state by one fixed step of `dt` ms. For `Rk45` this takes
+    /// the 5th-order solution without error control; use [`rkf45_step`] for
+    /// the adaptive variant.
+    pub fn step<F>(self, f: F, v: f64, u: f64, dt: f64) -> (f64, f64)
+    where
+        F: Fn(f64, f64) -> (f64, f64),
+    {
+        match self {
+            Self::Euler => {
+                let (dv, du) = f(v, u);
+                (v + dt * dv, u + dt * du)
+            }
+            Self::SplitEuler => {
+                let h = 0.5 * dt;
+                let v1 = v + h * f(v, u).0;
+                let v2 = v1 + h * f(v1, u).0;
+                let du = f(v2, u).1;
+                (v2, u + dt * du)
+            }
+            Self::SemiImplicitEuler => {
+                let v1 = v + dt * f(v, u).0;
+                (v1, u + dt * f(v1, u).1)
+            }
+            Self::Heun => {
+                let (dv1, du1) = f(v, u);
+                let (dv2, du2) = f(v + dt * dv1, u + dt * du1);
+                (v + 0.5 * dt * (dv1 + dv2), u + 0.5 * dt * (du1 + du2))
+            }
+            Self::Rk4 => {
+                let (k1v, k1u) = f(v, u);
+                let (k2v, k2u) = f(v + 0.5 * dt * k1v, u + 0.5 * dt * k1u);
+                let (k3v, k3u) = f(v + 0.5 * dt * k2v, u + 0.5 * dt * k2u);
+                let (k4v, k4u) = f(v + dt * k3v, u + dt * k3u);
+                (
+                    v + dt / 6.0 * (k1v + 2.0 * k2v + 2.0 * k3v + k4v),
+                    u + dt / 6.0 * (k1u + 2.0 * k2u + 2.0 * k3u + k4u),
+                )
+            }
+            Self::Rk45 => {
+                let (v5, u5, _) = rkf45_step(f, v, u, dt);
+                (v5, u5)
+            }
+        }
+    }
+}
+
+/// One Runge-Kutta-Fehlberg step. Returns the 5th-order solution and the
+/// max-norm of its difference from the embedded 4th-order solution.
+pub fn rkf45_step<F>(f: F, v: f64, u: f64, dt: f64) -> (f64, f64, f64)
+where
+    F: Fn(f64, f64) -> (f64, f64),
+{
+    let (k1v, k1u) = f(v, u);
+    let (k2v, k2u) = f(v + dt * (k1v / 4.0), u + dt * (k1u / 4.0));
+    let (k3v, k3u) = f(
+        v + dt * (3.0 / 32.0 * k1v + 9.0 / 32.0 * k2v),
+        u + dt * (3.0 / 32.0 * k1u + 9.0 / 32.0 * k2u),
+    );
+    let (k4v, k4u) = f(
+        v + dt * (1932.0 / 2197.0 * k1v - 7200.0 / 2197.0 * k2v + 7296.0 / 2197.0 * k3v),
+        u + dt * (1932.0 / 2197.0 * k1u - 7200.0 / 2197.0 * k2u + 7296.0 / 2197.0 * k3u),
+    );
+    let (k5v, k5u) = f(
+        v + dt * (439.0 / 216.0 * k1v - 8.0 * k2v + 3680.0 / 513.0 * k3v - 845.0 / 4104.0 * k4v),
+        u + dt * (439.0 / 216.0 * k1u - 8.0 * k2u + 3680.0 / 513.0 * k3u - 845.0 / 4104.0 * k4u),
+    );
+    let (k6v, k6u) = f(
+        v + dt
+            * (-8.0 / 27.0 * k1v + 2.0 * k2v - 3544.0 / 2565.0 * k3v + 1859.0 / 4104.0 * k4v
+                - 11.0 / 40.0 * k5v),
+        u + dt
+            * (-8.0 / 27.0 * k1u + 2.0 * k2u - 3544.0 / 2565.0 * k3u + 1859.0 / 4104.0 * k4u
+                - 11.0 / 40.0 * k5u),
+    );
+
+    let v4 =
+        v + dt * (25.0 / 216.0 * k1v + 1408.0 / 2565.0 * k3v + 2197.0 / 4104.0 * k4v - k5v / 5.0);
+    let u4 =
+        u + dt * (25.0 / 216.0 * k1u + 1408.0 / 2565.0 * k3u + 2197.0 / 4104.0 * k4u - k5u / 5.0);
+    let v5 = v + dt
+        * (16.0 / 135.0 * k1v + 6656.0 / 12825.0 * k3v + 28561.0 / 56430.0 * k4v
+            - 9.0 / 50.0 * k5v
+            + 2.0 / 55.0 * k6v);
+    let u5 = u + dt
+        * (16.0 / 135.0 * k1u + 6656.0 / 12825.0 * k3u + 28561.0 / 56430.0 * k4u
+            - 9.0 / 50.0 * k5u
+            + 2.0 / 55.0 * k6u);
+
+    let err = (v5 - v4).abs().max((u5 - u4).abs());
+    (v5, u5, err)
+}
+
+/// Integrator together with its step-size settings and the step size the
+/// adaptive scheme carries between ticks.
+#[derive(Debug, Clone, Copy)]
+pub struct Stepper {
+    integrator: Integrator,
+    step_ms: f64,
+    tolerance: f64,
+    h_ms: f64,
+}
+
+impl Default for Stepper {
+    fn default() -> Self {
+        Self {
+            integrator: Integrator::Euler,
+            step_ms: DEFAULT_STEP_MS,
+            tolerance: 1e-3,
+            h_ms: DEFAULT_STEP_MS,
+        }
+    }
+}
+
+impl Stepper {
+    pub fn set_integrator(&mut self, integrator: Integrator) {
+        self.integrator = integrator;
+        self.h_ms = self.step_ms;
+    }
+
+    /// Ignores non-finite and non-positive values.
+    pub fn set_step_ms(&mut self, step_ms: f64) {
+        if step_ms.is_finite() && step_ms > 0.0 {
+            self.step_ms = step_ms.max(MIN_STEP_MS);
+            self.h_ms = self.step_ms;
+        }
+    }
+
+    /// Ignores non-finite and non-positive values.
+    pub fn set_tolerance(&mut self, tolerance: f64) {
+        if tolerance.is_finite() && tolerance > 0.0 {
+            self.tolerance = tolerance;
+        }
+    }
+
+    /// Drops the step size carried over by the adaptive scheme.
+    pub fn reset(&mut self) {
+        self.h_ms = self.step_ms;
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "integrator": self.integrator.name(),
+            "step_ms": self.step_ms,
+            "tolerance": self.tolerance,
+            "h_ms": self.h_ms,
+        })
+    }
+
+    pub fn restore(&mut self, value: &Value) {
+        if let Some(integrator) = value
+            .get("integrator")
+            .and_then(Value::as_str)
+            .and_then(Integrator::from_name)
+        {
+            self.set_integrator(integrator);
+        }
+        if let Some(step_ms) = value.get("step_ms").and_then(Value::as_f64) {
+            self.set_step_ms(step_ms);
+        }
+        if let Some(tolerance) = value.get("tolerance").and_then(Value::as_f64) {
+            self.set_tolerance(tolerance);
+        }
+        read(value, "h_ms", &mut self.h_ms);
+        self.h_ms = self.h_ms.clamp(MIN_STEP_MS, self.step_ms);
+    }
+
+    /// Advances `(v, u)` by at most `remaining_ms` and returns the step taken.
+    pub fn advance<F>(&mut self, f: F, v: &mut f64, u: &mut f64, remaining_ms: f64) -> f64
+    where
+        F: Fn(f64, f64) -> (f64, f64) + Copy,
+    {
+        if self.integrator != Integrator::Rk45 {
+            let dt_ms = remaining_ms.min(self.step_ms);
+            (*v, *u) = self.integrator.step(f, *v, *u, dt_ms);
+            return dt_ms;
+        }
+
+        loop {
+            let dt_ms = remaining_ms.min(self.h_ms);
+            let (mut v_next, mut u_next, err) = rkf45_step(f, *v, *u, dt_ms);
+            let finite = v_next.is_finite() && u_next.is_finite();
+            let accept = (finite && err <= self.tolerance) || dt_ms <= MIN_STEP_MS;
+            // At the smallest step a non-finite candidate is replaced by a
+            // plain RK4 step rather than accepted as is.
+            if accept && !finite {
+                (v_next, u_next) = Integrator::Rk4.step(f, *v, *u, dt_ms);
+            }
+            let factor = if !finite || !err.is_finite() {
+                0.2
+            } else if err > 0.0 {
+                (0.9 * (self.tolerance / err).powf(0.2)).clamp(0.2, 5.0)
+            } else {
+                5.0
+            };
+            // A step truncated to the end of the tick says nothing about the
+            // step size the dynamics allow, so only shrink on rejection there.
+            if !accept || dt_ms >= self.h_ms {
+                self.h_ms = (dt_ms * factor).clamp(MIN_STEP_MS, self.step_ms);
+            }
+            if accept {
+                *v = v_next;
+                *u = u_next;
+                return dt_ms;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Harmonic oscillator `v' = u`, `u' = -v`: `v = cos t`, `u = -sin t`.
+    fn oscillator(v: f64, u: f64) -> (f64, f64) {
+        (u, -v)
+    }
+
+    fn fixed_step_error(integrator: Integrator, dt: f64) -> f64 {
+        let (mut v, mut u) = (1.0, 0.0);
+        let steps = (1.0 / dt).round() as usize;
+        for _ in 0..steps {
+            (v, u) = integrator.step(oscillator, v, u, dt);
+        }
+        (v - 1f64.cos()).abs().max((u + 1f64.sin()).abs())
+    }
+
+    #[test]
+    fn rk4_matches_the_exact_trajectory_at_fourth_order() {
+        let coarse = fixed_step_error(Integrator::Rk4, 0.1);
+        let fine = fixed_step_error(Integrator::Rk4, 0.05);
+        assert!(fine < 1e-7, "error {fine}");
+        // Halving the step divides the error by about 2^4.
+        assert!(
+            (12.0..20.0).contains(&(coarse / fine)),
+            "ratio {}",
+            coarse / fine
+        );
+    }
+
+    #[test]
+    fn euler_schemes_converge_at_first_order() {
+        for integrator in [Integrator::Euler, Integrator::SemiImplicitEuler] {
+            let ratio = fixed_step_error(integrator, 0.01) / fixed_step_error(integrator, 0.005);
+            assert!((1.6..2.4).contains(&ratio), "{integrator:?}: ratio {ratio}");
+        }
+    }
+
+    #[test]
+    fn rkf45_keeps_the_error_within_tolerance() {
+        let mut stepper = Stepper::default();
+        stepper.set_integrator(Integrator::Rk45);
+        stepper.set_step_ms(0.5);
+        stepper.set_tolerance(1e-9);
+        let (mut v, mut u) = (1.0, 0.0);
+        let mut remaining = 3.0;
+        while remaining > 0.0 {
+            remaining -= stepper.advance(oscillator, &mut v, &mut u, remaining);
+        }
+        assert!((v - 3f64.cos()).abs() < 1e-7, "v = {v}");
+        assert!((u + 3f64.sin()).abs() < 1e-7, "u = {u}");
+    }
+}
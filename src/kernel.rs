@@ -0,0 +1,232 @@
+use serde_json::{json, Value};
+
+use crate::snapshot::read;
+
+/// Time course of the postsynaptic current emitted after each spike.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KernelShape {
+    /// `w e^(-t / tau)`
+    Exponential,
+    /// `w (t / tau) e^(1 - t / tau)`, peaking at `w` when `t = tau`.
+    Alpha,
+    /// `A (e^(-t / tau) - e^(-t / tau_rise))`, normalised to peak at `w`.
+    DualExponential,
+}
+
+impl KernelShape {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "exponential" | "exp" => Some(Self::Exponential),
+            "alpha" => Some(Self::Alpha),
+            "dual_exponential" | "difference_of_exponentials" | "biexponential" => {
+                Some(Self::DualExponential)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Exponential => "exponential",
+            Self::Alpha => "alpha",
+            Self::DualExponential => "dual_exponential",
+        }
+    }
+}
+
+/// Spike-triggered current kernel, advanced with its exact solution.
+#[derive(Debug, Clone, Copy)]
+pub struct SynapticKernel {
+    pub shape: KernelShape,
+    pub tau_ms: f64,
+    pub tau_rise_ms: f64,
+    pub weight: f64,
+    s1: f64,
+    s2: f64,
+}
+
+impl Default for SynapticKernel {
+    fn default() -> Self {
+        Self {
+            shape: KernelShape::Exponential,
+            tau_ms: 5.0,
+            tau_rise_ms: 1.0,
+            weight: 1.0,
+            s1: 0.0,
+            s2: 0.0,
+        }
+    }
+}
+
+impl SynapticKernel {
+    pub fn current(&self) -> f64 {
+        match self.shape {
+            KernelShape::Exponential => self.s1,
+            KernelShape::Alpha => self.s2,
+            KernelShape::DualExponential => self.s1 - self.s2,
+        }
+    }
+
+    pub fn decay(&mut self, dt_ms: f64) {
+        let decay = (-dt_ms / self.tau_ms).exp();
+        match self.shape {
+            KernelShape::Exponential => self.s1 *= decay,
+            KernelShape::Alpha => {
+                self.s2 = (self.s2 + self.s1 * dt_ms / self.tau_ms) * decay;
+                self.s1 *= decay;
+            }
+            KernelShape::DualExponential => {
+                self.s1 *= decay;
+                self.s2 *= (-dt_ms / self.rise_ms()).exp();
+            }
+        }
+    }
+
+    /// Adds the response to a spike that happened `lag_ms` ago.
+    pub fn trigger(&mut self, lag_ms: f64) {
+        let mut pulse = Self {
+            s1: 0.0,
+            s2: 0.0,
+            ..*self
+        };
+        match self.shape {
+            KernelShape::Exponential => pulse.s1 = self.weight,
+            KernelShape::Alpha => pulse.s1 = self.weight * std::f64::consts::E,
+            KernelShape::DualExponential => {
+                let norm = self.dual_exponential_norm();
+                pulse.s1 = self.weight * norm;
+                pulse.s2 = self.weight * norm;
+            }
+        }
+        pulse.decay(lag_ms);
+        self.s1 += pulse.s1;
+        self.s2 += pulse.s2;
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "shape": self.shape.name(),
+            "tau_ms": self.tau_ms,
+            "tau_rise_ms": self.tau_rise_ms,
+            "weight": self.weight,
+            "s1": self.s1,
+            "s2": self.s2,
+        })
+    }
+
+    pub fn restore(&mut self, value: &Value) {
+        if let Some(shape) = value
+            .get("shape")
+            .and_then(Value::as_str)
+            .and_then(KernelShape::from_name)
+        {
+            self.shape = shape;
+        }
+        read(value, "tau_ms", &mut self.tau_ms);
+        read(value, "tau_rise_ms", &mut self.tau_rise_ms);
+        read(value, "weight", &mut self.weight);
+        read(value, "s1", &mut self.s1);
+        read(value, "s2", &mut self.s2);
+    }
+
+    pub fn reset(&mut self) {
+        self.s1 = 0.0;
+        self.s2 = 0.0;
+    }
+
+    /// Rise time constant, kept apart from `tau_ms` so the difference of
+    /// exponentials does not vanish.
+    fn rise_ms(&self) -> f64 {
+        if (self.tau_rise_ms - self.tau_ms).abs() < 1e-6 * self.tau_ms {
+            self.tau_ms * 0.999
+        } else {
+            self.tau_rise_ms
+        }
+    }
+
+    fn dual_exponential_norm(&self) -> f64 {
+        let (tau_d, tau_r) = (self.tau_ms, self.rise_ms());
+        let t_peak = tau_d * tau_r / (tau_d - tau_r) * (tau_d / tau_r).ln();
+        1.0 / ((-t_peak / tau_d).exp() - (-t_peak / tau_r).exp())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kernel(shape: KernelShape) -> SynapticKernel {
+        SynapticKernel {
+            shape,
+            weight: 2.0,
+            ..SynapticKernel::default()
+        }
+    }
+
+    /// Response `t_ms` after a spike, advanced in 0.1 ms steps.
+    fn response(mut kernel: SynapticKernel, t_ms: f64) -> f64 {
+        kernel.trigger(0.0);
+        for _ in 0..(t_ms / 0.1).round() as usize {
+            kernel.decay(0.1);
+        }
+        kernel.current()
+    }
+
+    #[test]
+    fn shapes_follow_their_closed_forms() {
+        let (w, tau, tau_rise): (f64, f64, f64) = (2.0, 5.0, 1.0);
+        let t_peak = tau * tau_rise / (tau - tau_rise) * (tau / tau_rise).ln();
+        let difference = |t: f64| (-t / tau).exp() - (-t / tau_rise).exp();
+        for t in [0.5, 1.0, 5.0, 12.0] {
+            let expected = [
+                (KernelShape::Exponential, w * (-t / tau).exp()),
+                (KernelShape::Alpha, w * t / tau * (1.0 - t / tau).exp()),
+                (
+                    KernelShape::DualExponential,
+                    w * difference(t) / difference(t_peak),
+                ),
+            ];
+            for (shape, expected) in expected {
+                let error = (response(kernel(shape), t) - expected).abs();
+                assert!(error < 1e-9, "{shape:?} at {t} ms: {error}");
+            }
+        }
+    }
+
+    #[test]
+    fn peaks_are_the_weight() {
+        for shape in [
+            KernelShape::Exponential,
+            KernelShape::Alpha,
+            KernelShape::DualExponential,
+        ] {
+            let peak = (0..300)
+                .map(|k| response(kernel(shape), k as f64 * 0.1))
+                .fold(f64::MIN, f64::max);
+            assert!((peak - 2.0).abs() < 1e-3, "{shape:?} {peak}");
+        }
+        // Equal time constants do not make the dual exponential vanish.
+        let mut equal = kernel(KernelShape::DualExponential);
+        equal.tau_rise_ms = equal.tau_ms;
+        assert!((response(equal, equal.tau_ms) - 2.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn lagged_triggers_land_on_the_same_trajectory() {
+        for shape in [
+            KernelShape::Exponential,
+            KernelShape::Alpha,
+            KernelShape::DualExponential,
+        ] {
+            let mut lagged = kernel(shape);
+            lagged.trigger(0.3);
+            let mut on_time = kernel(shape);
+            on_time.trigger(0.0);
+            on_time.decay(0.3);
+            assert!(
+                (lagged.current() - on_time.current()).abs() < 1e-12,
+                "{shape:?}"
+            );
+        }
+    }
+}
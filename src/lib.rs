@@ -1,29 +1,169 @@
+pub mod conductance;
+mod delay;
+mod display;
+pub mod integrator;
+pub mod kernel;
+pub mod model;
+mod modulation;
+pub mod noise;
+mod plasticity;
+pub mod presets;
+pub mod rng;
+mod snapshot;
+mod staging;
+pub mod stimulus;
+mod stp;
+pub mod synapse;
+
+use conductance::{ConductanceInput, Conductances};
+use delay::Delays;
+use display::{ap_template, SpikeDisplay};
+use integrator::{Integrator, Stepper, DEFAULT_STEP_MS};
+use kernel::{KernelShape, SynapticKernel};
+use model::Equations;
+use modulation::Modulation;
+use noise::Noise;
+use plasticity::{Stdp, StdpRule};
+use presets::Preset;
 use rtsyn_plugin::prelude::*;
 use serde_json::Value;
+use snapshot::{SnapshotFiles, StateStatus};
+use staging::Staging;
+use stimulus::{Generator, Protocol};
+use stp::Stp;
+use synapse::{Synapse, INPUT_PREFIX, MAX_INPUTS};
+
+/// State a host restart returns `(v, u)` to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RestartMode {
+    /// The configured initial conditions `v0`, `u0`.
+    Initial,
+    /// The resting state of the current parameters, falling back to the
+    /// initial conditions when the model has none.
+    Rest,
+}
+
+impl RestartMode {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "initial" | "initial_conditions" => Some(Self::Initial),
+            "rest" => Some(Self::Rest),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Initial => "initial",
+            Self::Rest => "rest",
+        }
+    }
+}
 
 #[derive(Debug)]
 struct Izhikevich2003Neuron {
     i_syn: f64,
+    synapses: [Synapse; MAX_INPUTS],
+    // Highest `in_<k>` port past `MAX_INPUTS` that was fed, whose values are dropped.
+    ignored_input: Option<usize>,
+    delays: Delays,
+    // An input at or above this value on a tick is a presynaptic spike.
+    spike_threshold: f64,
+    stp: [Stp; MAX_INPUTS],
+    stdp: Stdp,
+    // Weighted sum of `synapses`, refreshed at the start of every tick.
+    i_inputs: f64,
+    conductances: Conductances,
+    v_coupled: f64,
+    g_gap: f64,
     v: f64,
     u: f64,
+    // Initial conditions, kept apart from the live `v`, `u` once running.
+    v0: f64,
+    u0: f64,
+    restart_mode: RestartMode,
     a: f64,
     b: f64,
     c: f64,
     d: f64,
+    // Edits of `a`..`d` not yet committed at a tick boundary.
+    staging: Staging,
+    modulation: Modulation,
+    // `a`..`d` after modulation, latched at the start of every tick.
+    modulated: [f64; 4],
+    v_peak: f64,
+    equations: Equations,
+    protocol: Option<&'static Protocol>,
+    protocol_t_ms: f64,
+    generator: Generator,
+    // Tick at which the generator's time origin lies, ticks of stimulus time
+    // carried over from a loaded snapshot, and the generator time at the
+    // start of the current sub-step.
+    stim_origin_tick: Option<u64>,
+    stim_offset_ticks: u64,
+    stim_t_ms: f64,
+    last_tick: Option<u64>,
+    snapshot_files: SnapshotFiles,
+    noise: Noise,
     v_mv: f64,
+    spikes_this_tick: u32,
+    total_spikes: u64,
+    last_spike_s: Option<f64>,
+    time_s: f64,
+    output_kernel: SynapticKernel,
+    spike_display: SpikeDisplay,
+    spike_hold_ms: f64,
+    ap_width_ms: f64,
+    stepper: Stepper,
 }
 
 impl Default for Izhikevich2003Neuron {
     fn default() -> Self {
         Self {
             i_syn: 0.0,
+            synapses: [Synapse::default(); MAX_INPUTS],
+            ignored_input: None,
+            delays: Delays::default(),
+            spike_threshold: 0.5,
+            stp: [Stp::default(); MAX_INPUTS],
+            stdp: Stdp::default(),
+            i_inputs: 0.0,
+            conductances: Conductances::default(),
+            v_coupled: -65.0,
+            g_gap: 0.0,
             v: -65.0,
             u: -13.0,
+            v0: -65.0,
+            u0: -13.0,
+            restart_mode: RestartMode::Initial,
             a: 0.02,
             b: 0.2,
             c: -65.0,
             d: 8.0,
+            staging: Staging::default(),
+            modulation: Modulation::default(),
+            modulated: [0.02, 0.2, -65.0, 8.0],
+            v_peak: 30.0,
+            equations: Equations::STANDARD,
+            protocol: None,
+            protocol_t_ms: 0.0,
+            generator: Generator::default(),
+            stim_origin_tick: None,
+            stim_offset_ticks: 0,
+            stim_t_ms: 0.0,
+            last_tick: None,
+            snapshot_files: SnapshotFiles::default(),
+            noise: Noise::default(),
             v_mv: -65.0,
+            spikes_this_tick: 0,
+            total_spikes: 0,
+            last_spike_s: None,
+            time_s: 0.0,
+            output_kernel: SynapticKernel::default(),
+            spike_display: SpikeDisplay::Raw,
+            spike_hold_ms: 0.0,
+            ap_width_ms: 1.5,
+            stepper: Stepper::default(),
         }
     }
 }
@@ -42,34 +182,132 @@ impl PluginDescriptor for Izhikevich2003Neuron {
     }
 
     fn inputs() -> &'static [&'static str] {
-        &["i_syn"]
+        &[
+            "i_syn",
+            "g_AMPA",
+            "g_NMDA",
+            "g_GABA_A",
+            "g_GABA_B",
+            "Coupled neuron voltage (mV)",
+            "mod_a",
+            "mod_b",
+            "mod_c",
+            "mod_d",
+            "Dopamine",
+        ]
     }
 
     fn outputs() -> &'static [&'static str] {
-        &["Membrane potential (V)", "Membrane potential (mV)"]
+        &[
+            "Membrane potential (V)",
+            "Membrane potential (mV)",
+            "Spike",
+            "Spikes this tick",
+            "Total spikes",
+            "Last spike time (s)",
+            "Time since last spike (s)",
+            "Synaptic output current",
+        ]
     }
 
     fn internal_variables() -> &'static [&'static str] {
-        &["v", "u"]
+        &[
+            "v",
+            "u",
+            "g_ampa",
+            "g_nmda",
+            "g_gaba_a",
+            "g_gaba_b",
+            "a_mod",
+            "b_mod",
+            "c_mod",
+            "d_mod",
+            "w_0",
+            "w_1",
+            "w_2",
+            "w_3",
+            "w_4",
+            "w_5",
+            "w_6",
+            "w_7",
+            "dopamine",
+            "elig_0",
+            "elig_1",
+            "elig_2",
+            "elig_3",
+            "elig_4",
+            "elig_5",
+            "elig_6",
+            "elig_7",
+            "ignored_inputs",
+            "state_status",
+        ]
     }
 
     fn default_vars() -> Vec<(&'static str, Value)> {
-        vec![
+        let mut vars = vec![
+            ("preset", "custom".into()),
             ("v", (-65.0).into()),
             ("u", (-13.0).into()),
+            ("restart_mode", "initial".into()),
             ("a", 0.02.into()),
             ("b", 0.2.into()),
             ("c", (-65.0).into()),
             ("d", 8.0.into()),
-        ]
+            ("param_ramp_ms", 0.0.into()),
+            ("mod_a_mode", "off".into()),
+            ("mod_b_mode", "off".into()),
+            ("mod_c_mode", "off".into()),
+            ("mod_d_mode", "off".into()),
+            ("v_peak", 30.0.into()),
+            ("k2", 0.04.into()),
+            ("k1", 5.0.into()),
+            ("k0", 140.0.into()),
+            ("integrator", "euler".into()),
+            ("step_ms", DEFAULT_STEP_MS.into()),
+            ("tolerance", 1e-3.into()),
+            ("conductance_input", "clamp".into()),
+            ("e_ampa", 0.0.into()),
+            ("tau_ampa", 5.0.into()),
+            ("e_nmda", 0.0.into()),
+            ("tau_nmda", 150.0.into()),
+            ("e_gaba_a", (-70.0).into()),
+            ("tau_gaba_a", 6.0.into()),
+            ("e_gaba_b", (-90.0).into()),
+            ("tau_gaba_b", 150.0.into()),
+            ("g_gap", 0.0.into()),
+            ("syn_kernel", "exponential".into()),
+            ("syn_tau_ms", 5.0.into()),
+            ("syn_tau_rise_ms", 1.0.into()),
+            ("syn_weight", 1.0.into()),
+            ("input_gains", Value::Array(Vec::new())),
+            ("input_signs", Value::Array(Vec::new())),
+            ("input_delays_ms", Value::Array(Vec::new())),
+            ("input_spike_threshold", 0.5.into()),
+            ("stp_u", Value::Array(Vec::new())),
+            ("stp_tau_rec_ms", Value::Array(Vec::new())),
+            ("stp_tau_facil_ms", Value::Array(Vec::new())),
+            ("spike_display", "raw".into()),
+            ("spike_hold_ms", 0.0.into()),
+            ("ap_width_ms", 1.5.into()),
+            ("save_state", "".into()),
+            ("load_state", "".into()),
+        ];
+        vars.extend(Generator::default_vars());
+        vars.extend(Noise::default_vars());
+        vars.extend(Stdp::default_vars());
+        vars
     }
 
     fn behavior() -> PluginBehavior {
         PluginBehavior {
             supports_start_stop: true,
             supports_restart: true,
-            supports_apply: false,
-            extendable_inputs: ExtendableInputs::None,
+            supports_apply: true,
+            // Only `in_0` .. `in_7` are used; see `ignored_inputs`.
+            extendable_inputs: ExtendableInputs::Auto {
+                pattern: format!("{INPUT_PREFIX}{{}}"),
+            },
             loads_started: false,
             external_window: false,
             starts_expanded: true,
@@ -79,63 +317,446 @@ impl PluginDescriptor for Izhikevich2003Neuron {
     }
 }
 
+impl Izhikevich2003Neuron {
+    /// Handles `input_gains`/`input_signs` arrays and the per-input
+    /// `in_<k>_gain`/`in_<k>_sign` keys. Returns whether `key` was one of them.
+    fn set_synapse_config(&mut self, key: &str, value: &Value) -> bool {
+        let items = value.as_array().map(Vec::as_slice).unwrap_or_default();
+        match key {
+            "input_gains" => {
+                for (slot, item) in self.synapses.iter_mut().zip(items) {
+                    if let Some(gain) = item.as_f64().filter(|g| g.is_finite()) {
+                        slot.gain = gain;
+                    }
+                }
+                return true;
+            }
+            "input_signs" => {
+                for (slot, item) in self.synapses.iter_mut().zip(items) {
+                    if let Some(sign) = synapse::parse_sign(item) {
+                        slot.sign = sign;
+                    }
+                }
+                return true;
+            }
+            _ => {}
+        }
+
+        let Some((port, field)) = key.rsplit_once('_') else {
+            return false;
+        };
+        let Some(k) = synapse::input_index(port) else {
+            return false;
+        };
+        match field {
+            "gain" => {
+                if let Some(gain) = value.as_f64().filter(|g| g.is_finite()) {
+                    self.synapses[k].gain = gain;
+                }
+            }
+            "sign" => {
+                if let Some(sign) = synapse::parse_sign(value) {
+                    self.synapses[k].sign = sign;
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Accepts a snapshot object, the snapshot as a JSON string, or the path
+    /// of a file holding it, which is read on a worker thread and restored at
+    /// the start of a tick. Failures show in `state_status`.
+    fn load_state(&mut self, value: &Value) {
+        let result = match value {
+            Value::Object(_) => self.restore(value),
+            Value::String(source) if source.trim_start().starts_with('{') => {
+                match serde_json::from_str(source) {
+                    Ok(snapshot) => self.restore(&snapshot),
+                    Err(e) => Err(e.to_string()),
+                }
+            }
+            Value::String(path) if !path.trim().is_empty() => {
+                self.snapshot_files.read(path.trim().to_string());
+                return;
+            }
+            _ => return,
+        };
+        self.snapshot_files.status = match result {
+            Ok(()) => StateStatus::Done,
+            Err(_) => StateStatus::Failed,
+        };
+    }
+
+    fn apply_preset(&mut self, preset: &Preset) {
+        self.a = preset.a;
+        self.b = preset.b;
+        self.c = preset.c;
+        self.d = preset.d;
+        self.staging.clear();
+        self.set_initial_conditions(preset.v0, preset.u0());
+        self.equations = preset.equations;
+        self.protocol = preset.protocol;
+        self.protocol_t_ms = 0.0;
+        if let Some(integrator) = preset.integrator {
+            self.stepper.set_integrator(integrator);
+        }
+        if let Some(step_ms) = preset.step_ms {
+            self.stepper.set_step_ms(step_ms);
+        }
+    }
+
+    /// `a`, `b`, `c`, `d`, in the order of [`staging::PARAMS`].
+    fn parameters(&self) -> [f64; 4] {
+        [self.a, self.b, self.c, self.d]
+    }
+
+    fn set_parameters(&mut self, [a, b, c, d]: [f64; 4]) {
+        (self.a, self.b, self.c, self.d) = (a, b, c, d);
+    }
+
+    /// Stages an edit of `a`..`d`; returns whether `key` is one of them.
+    /// Before the first tick nothing runs on the old values, so the edit
+    /// takes effect at once, as the initial conditions do.
+    fn stage_parameter(&mut self, key: &str, x: f64) -> bool {
+        if !self.staging.stage(key, x) {
+            return false;
+        }
+        if self.last_tick.is_none() {
+            let mut parameters = self.parameters();
+            self.staging.finish(&mut parameters);
+            self.set_parameters(parameters);
+        }
+        true
+    }
+
+    /// Stores the initial conditions. They also become the live state until
+    /// the first tick; afterwards they only take effect on a restart.
+    fn set_initial_conditions(&mut self, v0: f64, u0: f64) {
+        self.v0 = v0;
+        self.u0 = u0;
+        if self.last_tick.is_none() {
+            self.v = v0;
+            self.u = u0;
+            self.v_mv = v0;
+        }
+    }
+
+    /// Returns to the state of a fresh run: `(v, u)` per `restart_mode`, no
+    /// spikes, stimulus and noise from their start, all synaptic state cleared.
+    fn restart(&mut self) {
+        let mut parameters = self.parameters();
+        self.staging.finish(&mut parameters);
+        self.set_parameters(parameters);
+        let rest = match self.restart_mode {
+            RestartMode::Initial => None,
+            RestartMode::Rest => self.equations.rest(self.modulated[1]),
+        };
+        (self.v, self.u) = rest.unwrap_or((self.v0, self.u0));
+        self.v_mv = self.v;
+        self.spikes_this_tick = 0;
+        self.total_spikes = 0;
+        self.last_spike_s = None;
+        self.time_s = 0.0;
+        self.stim_origin_tick = None;
+        self.stim_offset_ticks = 0;
+        self.stim_t_ms = 0.0;
+        self.protocol_t_ms = 0.0;
+        self.conductances.reset();
+        self.output_kernel.reset();
+        self.noise.reset();
+        self.stdp.reset();
+        self.delays.clear();
+        self.stp.iter_mut().for_each(Stp::reset);
+        self.synapses.iter_mut().for_each(|s| s.efficacy = 1.0);
+        self.stepper.reset();
+    }
+
+    /// Value latched into the membrane-potential outputs at the end of a tick.
+    fn display_voltage(&self) -> f64 {
+        let Some(last_spike_s) = self.last_spike_s else {
+            return self.v;
+        };
+        let since_ms = (self.time_s - last_spike_s) * 1000.0;
+        let spiked = self.spikes_this_tick > 0;
+
+        match self.spike_display {
+            SpikeDisplay::Raw => self.v,
+            SpikeDisplay::Peak if spiked || since_ms < self.spike_hold_ms => self.v_peak,
+            // Periods longer than the template cannot resolve it; show the peak.
+            SpikeDisplay::Waveform if spiked && since_ms >= self.ap_width_ms => self.v_peak,
+            SpikeDisplay::Waveform if since_ms < self.ap_width_ms => {
+                ap_template(self.v, self.v_peak, since_ms, self.ap_width_ms)
+            }
+            _ => self.v,
+        }
+    }
+
+    fn advance(&mut self, remaining_ms: f64) -> f64 {
+        let dt_ms = self.integrate(remaining_ms);
+        // White noise is split off the deterministic step (Euler-Maruyama),
+        // which keeps its scaling right for every integrator.
+        self.v += self.noise.advance(dt_ms);
+        self.conductances.decay(dt_ms);
+        self.output_kernel.decay(dt_ms);
+        self.stdp.advance(&mut self.synapses, dt_ms);
+        if self.protocol.is_some() {
+            self.protocol_t_ms += dt_ms;
+        }
+        dt_ms
+    }
+
+    fn integrate(&mut self, remaining_ms: f64) -> f64 {
+        let i = self.i_syn
+            + self.i_inputs
+            + self.generator.current(self.stim_t_ms)
+            + self.noise.current()
+            + self.protocol.map_or(0.0, |p| p.current(self.protocol_t_ms));
+        let [a, b, _, _] = self.modulated;
+        let rhs = self.equations.rhs(a, b, i);
+        let (conductances, g_gap, v_coupled) = (self.conductances, self.g_gap, self.v_coupled);
+        // Conductances and the coupled voltage are held over the sub-step, but
+        // the currents they drive follow `v`.
+        let f = move |v: f64, u: f64| {
+            let (dv, du) = rhs(v, u);
+            (dv + conductances.current(v) + g_gap * (v_coupled - v), du)
+        };
+        self.stepper
+            .advance(f, &mut self.v, &mut self.u, remaining_ms)
+    }
+}
+
 impl PluginRuntime for Izhikevich2003Neuron {
     fn set_config_value(&mut self, key: &str, value: &Value) {
+        if self.set_synapse_config(key, value)
+            || self.modulation.set_mode(key, value)
+            || stp::set_config(&mut self.stp, key, value)
+        {
+            return;
+        }
+
+        if let (Some(on), "stdp_dopamine") = (value.as_bool(), key) {
+            self.stdp.dopamine_gated = on;
+            return;
+        }
+
+        if key == "load_state" {
+            self.load_state(value);
+            return;
+        }
+
+        if key == "input_delays_ms" {
+            self.delays.set_config(value);
+            return;
+        }
+
+        if let Some(name) = value.as_str() {
+            match key {
+                "integrator" => {
+                    if let Some(integrator) = Integrator::from_name(name) {
+                        self.stepper.set_integrator(integrator);
+                    }
+                }
+                "syn_kernel" => {
+                    if let Some(shape) = KernelShape::from_name(name) {
+                        self.output_kernel.shape = shape;
+                        self.output_kernel.reset();
+                    }
+                }
+                "spike_display" => {
+                    if let Some(mode) = SpikeDisplay::from_name(name) {
+                        self.spike_display = mode;
+                    }
+                }
+                "stdp_rule" => {
+                    if let Some(rule) = StdpRule::from_name(name) {
+                        self.stdp.rule = rule;
+                    }
+                }
+                "conductance_input" => {
+                    if let Some(mode) = ConductanceInput::from_name(name) {
+                        self.conductances.input_mode = mode;
+                    }
+                }
+                "restart_mode" => {
+                    if let Some(mode) = RestartMode::from_name(name) {
+                        self.restart_mode = mode;
+                    }
+                }
+                "preset" => {
+                    if let Some(preset) = presets::find(name) {
+                        self.apply_preset(preset);
+                    }
+                }
+                "save_state" if !name.trim().is_empty() => {
+                    let snapshot = self.snapshot();
+                    self.snapshot_files.write(name.trim().to_string(), snapshot);
+                }
+                _ => {}
+            }
+            return;
+        }
+
         if let Some(v) = value.as_f64() {
+            if v.is_finite()
+                && (self.generator.set(key, v)
+                    || self.noise.set(key, v)
+                    || self.stdp.set(key, v)
+                    || self.stage_parameter(key, v))
+            {
+                return;
+            }
             match key {
-                "v" => self.v = v,
-                "u" => self.u = v,
-                "a" => self.a = v,
-                "b" => self.b = v,
-                "c" => self.c = v,
-                "d" => self.d = v,
+                "v" => self.set_initial_conditions(v, self.u0),
+                "u" => self.set_initial_conditions(self.v0, v),
+                "param_ramp_ms" if v.is_finite() => self.staging.ramp_ms = v.max(0.0),
+                "v_peak" => self.v_peak = v,
+                "k2" => self.equations.k2 = v,
+                "k1" => self.equations.k1 = v,
+                "k0" => self.equations.k0 = v,
+                "e_ampa" => self.conductances.ampa.e_rev = v,
+                "tau_ampa" => self.conductances.ampa.tau_ms = v,
+                "e_nmda" => self.conductances.nmda.e_rev = v,
+                "tau_nmda" => self.conductances.nmda.tau_ms = v,
+                "e_gaba_a" => self.conductances.gaba_a.e_rev = v,
+                "tau_gaba_a" => self.conductances.gaba_a.tau_ms = v,
+                "e_gaba_b" => self.conductances.gaba_b.e_rev = v,
+                "tau_gaba_b" => self.conductances.gaba_b.tau_ms = v,
+                "g_gap" => self.g_gap = v,
+                // `stdp_spike_threshold` is the name the key had before STP used it too.
+                "input_spike_threshold" | "stdp_spike_threshold" => self.spike_threshold = v,
+                "syn_tau_ms" if v > 0.0 => self.output_kernel.tau_ms = v,
+                "syn_tau_rise_ms" if v > 0.0 => self.output_kernel.tau_rise_ms = v,
+                "syn_weight" => self.output_kernel.weight = v,
+                "step_ms" => self.stepper.set_step_ms(v),
+                "tolerance" => self.stepper.set_tolerance(v),
+                "spike_hold_ms" if v.is_finite() => self.spike_hold_ms = v.max(0.0),
+                "ap_width_ms" if v.is_finite() && v > 0.0 => self.ap_width_ms = v,
                 _ => {}
             }
         }
     }
 
     fn set_input_value(&mut self, key: &str, v: f64) {
-        match key {
-            "i_syn" => self.i_syn = if v.is_finite() { v } else { 0.0 },
-            _ => {}
+        let v = if v.is_finite() { v } else { 0.0 };
+        if key == "i_syn" {
+            self.i_syn = v;
+        } else if key == "Coupled neuron voltage (mV)" {
+            self.v_coupled = v;
+        } else if let Some(k) = synapse::input_index(key) {
+            self.delays.lines[k].input = v;
+        } else if let Some(k) = synapse::ignored_input_index(key) {
+            self.ignored_input = self.ignored_input.max(Some(k));
+        } else if let Some(x) = self.modulation.input_mut(key) {
+            *x = v;
+        } else if key == "Dopamine" {
+            self.stdp.dopamine_input = v;
+        } else if let Some(c) = key
+            .strip_prefix("g_")
+            .and_then(|receptor| self.conductances.get_mut(receptor))
+        {
+            c.input = v;
         }
     }
 
-    fn process_tick(&mut self, _tick: u64, period_seconds: f64) {
+    fn process_tick(&mut self, tick: u64, period_seconds: f64) {
         if !period_seconds.is_finite() || period_seconds <= 0.0 {
             return;
         }
 
+        if let Some(snapshot) = self.snapshot_files.poll() {
+            if self.restore(&snapshot).is_err() {
+                self.snapshot_files.status = StateStatus::Failed;
+            }
+        }
+        // A tick counter that goes backwards means the host restarted.
+        if self.last_tick.is_some_and(|last| tick < last) {
+            self.restart();
+        }
+        let tick_start_s = tick as f64 * period_seconds;
+        // Spike times restored from a snapshot move onto the host's clock.
+        if self.last_tick.is_none() {
+            if let Some(last_spike_s) = &mut self.last_spike_s {
+                *last_spike_s += tick_start_s - self.time_s;
+            }
+        }
+        self.last_tick = Some(tick);
+        let stim_origin_tick = *self.stim_origin_tick.get_or_insert(tick);
+
+        // Staged edits land together, before any sub-step of this tick.
+        let mut parameters = self.parameters();
+        self.staging
+            .commit(&mut parameters, period_seconds * 1000.0);
+        self.set_parameters(parameters);
+        self.modulated = self.modulation.apply(parameters);
+
+        self.spikes_this_tick = 0;
+        self.stdp.latch_dopamine();
+        // Inputs take effect after their conduction delay.
+        self.delays.observe_period(period_seconds * 1000.0);
+        for (slot, line) in self.synapses.iter_mut().zip(&mut self.delays.lines) {
+            slot.value = line.shift(period_seconds * 1000.0);
+        }
+        let spiked = self.synapses.map(|s| s.value >= self.spike_threshold);
+        for ((slot, stp), spiked) in self.synapses.iter_mut().zip(&mut self.stp).zip(spiked) {
+            stp.tick(spiked, period_seconds * 1000.0);
+            slot.efficacy = stp.efficacy();
+        }
+        self.stdp.on_pre(&mut self.synapses, spiked);
+        self.i_inputs = self.synapses.iter().map(Synapse::current).sum();
+        self.conductances.latch_inputs();
+
         // Izhikevich model equations are defined in ms.
-        let mut remaining_ms = period_seconds * 1000.0;
-        const MAX_STEP_MS: f64 = 0.5;
+        let period_ms = period_seconds * 1000.0;
+        let mut remaining_ms = period_ms;
+        let stim_tick_ms = (tick - stim_origin_tick + self.stim_offset_ticks) as f64 * period_ms;
 
         while remaining_ms > 0.0 {
-            let dt_ms = remaining_ms.min(MAX_STEP_MS);
+            let elapsed_ms = period_ms - remaining_ms;
+            self.stim_t_ms = stim_tick_ms + elapsed_ms;
+            let (v0, u0) = (self.v, self.u);
+            let dt_ms = self.advance(remaining_ms);
             remaining_ms -= dt_ms;
 
-            let v0 = self.v;
-            let u0 = self.u;
+            // Higher-order schemes can overshoot to non-finite values on the
+            // upstroke; treat that as a spike too so every scheme resets alike.
+            if self.v >= self.v_peak || !self.v.is_finite() {
+                // Linear interpolation of the peak crossing within the sub-step.
+                let frac = if self.v.is_finite() && self.v > v0 {
+                    ((self.v_peak - v0) / (self.v - v0)).clamp(0.0, 1.0)
+                } else {
+                    1.0
+                };
+                self.last_spike_s = Some(tick_start_s + (elapsed_ms + frac * dt_ms) / 1000.0);
+                self.output_kernel.trigger((1.0 - frac) * dt_ms);
 
-            let dv = 0.04 * v0 * v0 + 5.0 * v0 + 140.0 - u0 + self.i_syn;
-            let du = self.a * (self.b * v0 - u0);
-
-            self.v = v0 + dt_ms * dv;
-            self.u = u0 + dt_ms * du;
-
-            if self.v >= 30.0 {
-                self.v = self.c;
-                self.u += self.d;
+                let [_, _, c, d] = self.modulated;
+                self.v = c;
+                // A blown-up step leaves no usable `u`; reset from its last value.
+                if !self.u.is_finite() {
+                    self.u = u0;
+                }
+                self.u += d;
+                self.stdp.on_post(&mut self.synapses);
+                self.spikes_this_tick += 1;
+                self.total_spikes += 1;
             }
         }
 
-        self.v_mv = self.v;
+        self.time_s = tick_start_s + period_seconds;
+        self.v_mv = self.display_voltage();
     }
 
     fn get_output_value(&self, key: &str) -> f64 {
         match key {
             "Membrane potential (V)" => self.v_mv / 1000.0,
             "Membrane potential (mV)" => self.v_mv,
+            "Spike" => f64::from(u8::from(self.spikes_this_tick > 0)),
+            "Spikes this tick" => f64::from(self.spikes_this_tick),
+            "Total spikes" => self.total_spikes as f64,
+            "Last spike time (s)" => self.last_spike_s.unwrap_or(0.0),
+            "Time since last spike (s)" => self.time_s - self.last_spike_s.unwrap_or(0.0),
+            "Synaptic output current" => self.output_kernel.current(),
             _ => 0.0,
         }
     }
@@ -144,9 +765,153 @@ impl PluginRuntime for Izhikevich2003Neuron {
         match key {
             "v" => Some(self.v),
             "u" => Some(self.u),
-            _ => None,
+            "g_ampa" => Some(self.conductances.ampa.g),
+            "g_nmda" => Some(self.conductances.nmda.g),
+            "g_gaba_a" => Some(self.conductances.gaba_a.g),
+            "g_gaba_b" => Some(self.conductances.gaba_b.g),
+            "a_mod" => Some(self.modulated[0]),
+            "b_mod" => Some(self.modulated[1]),
+            "c_mod" => Some(self.modulated[2]),
+            "d_mod" => Some(self.modulated[3]),
+            "dopamine" => Some(self.stdp.dopamine()),
+            // Ports are added in order, so `in_8` .. `in_<k>` are all past the limit.
+            "state_status" => Some(self.snapshot_files.status.value()),
+            "ignored_inputs" => Some(self.ignored_input.map_or(0, |k| k + 1 - MAX_INPUTS) as f64),
+            _ => {
+                if let Some(k) = key.strip_prefix("elig_") {
+                    return self.stdp.eligibility(k.parse().ok()?);
+                }
+                let k = key.strip_prefix("w_")?.parse::<usize>().ok()?;
+                Some(self.synapses.get(k)?.gain)
+            }
         }
     }
 }
 
-rtsyn_plugin::export_plugin!(Izhikevich2003Neuron);
+// The exported symbols get a module of their own so that crates linking this
+// one as a library never pull them in along with shared code.
+#[cfg(feature = "plugin")]
+mod export {
+    use super::Izhikevich2003Neuron;
+
+    rtsyn_plugin::export_plugin!(Izhikevich2003Neuron);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first_spike_s(step_ms: f64, period_s: f64) -> f64 {
+        let mut neuron = Izhikevich2003Neuron::default();
+        neuron.set_config_value("integrator", &Value::from("rk4"));
+        neuron.set_config_value("step_ms", &Value::from(step_ms));
+        neuron.set_input_value("i_syn", 10.0);
+        let mut tick = 0;
+        while neuron.total_spikes == 0 {
+            neuron.process_tick(tick, period_s);
+            tick += 1;
+        }
+        neuron.last_spike_s.unwrap()
+    }
+
+    #[test]
+    fn spike_time_is_interpolated_within_the_sub_step() {
+        let reference = first_spike_s(0.001, 1e-3);
+        let coarse = first_spike_s(0.1, 1e-3);
+        // Without interpolation the time would sit on the 0.1 ms grid, up to
+        // a whole sub-step late.
+        assert!((coarse * 10_000.0).fract() > 1e-3);
+        assert!(
+            (coarse - reference).abs() < 0.02e-3,
+            "{coarse} vs {reference}"
+        );
+    }
+
+    #[test]
+    fn a_blown_up_step_resets_to_finite_state() {
+        for integrator in ["euler", "rk4", "rk45"] {
+            let mut neuron = Izhikevich2003Neuron::default();
+            neuron.set_config_value("integrator", &Value::from(integrator));
+            neuron.set_input_value("i_syn", 1e300);
+            neuron.process_tick(0, 1e-3);
+            assert!(neuron.total_spikes > 0, "{integrator}");
+            assert!(neuron.v.is_finite() && neuron.u.is_finite(), "{integrator}");
+        }
+    }
+
+    #[test]
+    fn inputs_are_weighted_and_extra_ports_ignored() {
+        let mut neuron = Izhikevich2003Neuron::default();
+        neuron.set_config_value("input_gains", &serde_json::json!([2.0, 3.0]));
+        neuron.set_config_value("in_1_sign", &Value::from("inhibitory"));
+        neuron.set_input_value("in_0", 5.0);
+        neuron.set_input_value("in_1", 1.0);
+        neuron.set_input_value("in_9", 100.0);
+        neuron.process_tick(0, 1e-4);
+        assert_eq!(neuron.i_inputs, 2.0 * 5.0 - 3.0);
+        assert_eq!(neuron.get_internal_value("ignored_inputs"), Some(2.0));
+    }
+
+    #[test]
+    fn gap_junction_pulls_v_towards_the_coupled_neuron() {
+        let run = |g_gap: f64, v_coupled: f64| {
+            let mut neuron = Izhikevich2003Neuron::default();
+            neuron.set_config_value("g_gap", &Value::from(g_gap));
+            neuron.set_input_value("Coupled neuron voltage (mV)", v_coupled);
+            (0..2000).for_each(|tick| neuron.process_tick(tick, 1e-4));
+            neuron
+        };
+        assert_eq!(run(0.0, 0.0).total_spikes, 0);
+        assert!(run(0.5, 0.0).total_spikes > 0);
+        let rest = run(0.0, -80.0).v;
+        assert!(run(0.5, -80.0).v < rest - 1.0);
+    }
+
+    #[test]
+    fn parameter_edits_are_committed_at_tick_boundaries() {
+        let mut neuron = Izhikevich2003Neuron::default();
+        // Before the first tick nothing has run on the old values.
+        neuron.set_config_value("c", &Value::from(-50.0));
+        assert_eq!(neuron.c, -50.0);
+        neuron.process_tick(0, 1e-4);
+        neuron.set_config_value("d", &Value::from(2.0));
+        assert_eq!(neuron.d, 8.0);
+        neuron.process_tick(1, 1e-4);
+        assert_eq!((neuron.c, neuron.d), (-50.0, 2.0));
+    }
+
+    #[test]
+    fn modulation_acts_on_the_committed_parameters() {
+        let mut neuron = Izhikevich2003Neuron::default();
+        neuron.set_config_value("mod_c_mode", &Value::from("override"));
+        neuron.set_config_value("mod_a_mode", &Value::from("scale"));
+        neuron.set_input_value("mod_c", -45.0);
+        neuron.set_input_value("mod_a", 2.0);
+        neuron.set_input_value("i_syn", 10.0);
+        let mut resets = Vec::new();
+        for tick in 0..2000 {
+            neuron.process_tick(tick, 1e-4);
+            if neuron.spikes_this_tick > 0 {
+                resets.push(neuron.v);
+            }
+        }
+        assert_eq!(neuron.get_internal_value("c_mod"), Some(-45.0));
+        assert_eq!(neuron.get_internal_value("a_mod"), Some(0.04));
+        assert_eq!((neuron.a, neuron.c), (0.02, -65.0));
+        // Each reset lands on the modulated `c`, then moves on within the tick.
+        assert!(!resets.is_empty() && resets.iter().all(|v| (v + 45.0).abs() < 1.0));
+    }
+
+    #[test]
+    fn stdp_spike_threshold_is_still_accepted() {
+        let mut neuron = Izhikevich2003Neuron::default();
+        neuron.set_config_value("stdp_spike_threshold", &Value::from(0.2));
+        assert_eq!(neuron.spike_threshold, 0.2);
+        let mut snapshot = neuron.snapshot();
+        let parameters = snapshot["parameters"].as_object_mut().unwrap();
+        parameters.remove("input_spike_threshold");
+        parameters.insert("stdp_spike_threshold".into(), Value::from(0.7));
+        neuron.set_config_value("load_state", &snapshot);
+        assert_eq!(neuron.spike_threshold, 0.7);
+    }
+}
@@ -0,0 +1,173 @@
+use serde_json::{json, Value};
+
+use crate::snapshot::read;
+
+/// Form of the recovery-variable equation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recovery {
+    /// `u' = a (b v - u)`
+    Standard,
+    /// `u' = a b (v + 65)`, used by the accommodation panel of Izhikevich (2004).
+    Accommodation,
+}
+
+impl Recovery {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "standard" => Some(Self::Standard),
+            "accommodation" => Some(Self::Accommodation),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Standard => "standard",
+            Self::Accommodation => "accommodation",
+        }
+    }
+}
+
+/// Right-hand side of the model: `v' = k2 v^2 + k1 v + k0 - u + I`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Equations {
+    pub k2: f64,
+    pub k1: f64,
+    pub k0: f64,
+    pub recovery: Recovery,
+}
+
+impl Equations {
+    pub const STANDARD: Self = Self {
+        k2: 0.04,
+        k1: 5.0,
+        k0: 140.0,
+        recovery: Recovery::Standard,
+    };
+
+    pub fn dv(&self, v: f64, u: f64, i: f64) -> f64 {
+        self.k2 * v * v + self.k1 * v + self.k0 - u + i
+    }
+
+    pub fn du(&self, a: f64, b: f64, v: f64, u: f64) -> f64 {
+        match self.recovery {
+            Recovery::Standard => a * (b * v - u),
+            Recovery::Accommodation => a * b * (v + 65.0),
+        }
+    }
+
+    /// Resting state `(v, u)` without input: the lower (stable) root of
+    /// `v' = u' = 0`. `None` when the nullclines do not intersect.
+    pub fn rest(&self, b: f64) -> Option<(f64, f64)> {
+        let v = match self.recovery {
+            Recovery::Accommodation => -65.0,
+            Recovery::Standard if self.k2 == 0.0 => -self.k0 / (self.k1 - b),
+            Recovery::Standard => {
+                let slope = self.k1 - b;
+                let disc = slope * slope - 4.0 * self.k2 * self.k0;
+                (-slope - disc.sqrt()) / (2.0 * self.k2)
+            }
+        };
+        let u = match self.recovery {
+            Recovery::Standard => b * v,
+            Recovery::Accommodation => self.k2 * v * v + self.k1 * v + self.k0,
+        };
+        (v.is_finite() && u.is_finite()).then_some((v, u))
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "k2": self.k2,
+            "k1": self.k1,
+            "k0": self.k0,
+            "recovery": self.recovery.name(),
+        })
+    }
+
+    pub fn restore(&mut self, value: &Value) {
+        read(value, "k2", &mut self.k2);
+        read(value, "k1", &mut self.k1);
+        read(value, "k0", &mut self.k0);
+        if let Some(recovery) = value
+            .get("recovery")
+            .and_then(Value::as_str)
+            .and_then(Recovery::from_name)
+        {
+            self.recovery = recovery;
+        }
+    }
+
+    /// `(v', u')` for the given parameters and input, as used by the integrators.
+    pub fn rhs(self, a: f64, b: f64, i: f64) -> impl Fn(f64, f64) -> (f64, f64) + Copy {
+        move |v, u| (self.dv(v, u, i), self.du(a, b, v, u))
+    }
+}
+
+impl Default for Equations {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rest_is_the_stable_fixed_point() {
+        for (equations, b) in [
+            (Equations::STANDARD, 0.2),
+            (Equations::STANDARD, 0.25),
+            (
+                Equations {
+                    k1: 4.1,
+                    k0: 108.0,
+                    ..Equations::STANDARD
+                },
+                -0.1,
+            ),
+            (
+                Equations {
+                    k2: 0.0,
+                    k1: -1.0,
+                    ..Equations::STANDARD
+                },
+                0.2,
+            ),
+        ] {
+            let (v, u) = equations.rest(b).unwrap();
+            let (dv, du) = equations.rhs(0.02, b, 0.0)(v, u);
+            assert!(dv.abs() < 1e-9 && du.abs() < 1e-9, "{equations:?}");
+            // Below the threshold root: a small depolarisation decays.
+            let (dv, _) = equations.rhs(0.02, b, 0.0)(v + 0.1, u);
+            assert!(dv < 0.0, "{equations:?}");
+        }
+        // RS rest of the 2003 paper, about -70 mV.
+        let (v, _) = Equations::STANDARD.rest(0.2).unwrap();
+        assert!((v + 70.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn nullclines_that_do_not_meet_have_no_rest() {
+        let tonic = Equations {
+            k0: 200.0,
+            ..Equations::STANDARD
+        };
+        assert_eq!(tonic.rest(0.2), None);
+    }
+
+    #[test]
+    fn accommodation_recovery_ignores_u() {
+        let equations = Equations {
+            recovery: Recovery::Accommodation,
+            ..Equations::STANDARD
+        };
+        assert_eq!(equations.du(0.02, 1.0, -55.0, 3.0), 0.2);
+        assert_eq!(equations.du(0.02, 1.0, -55.0, -3.0), 0.2);
+        assert_eq!(equations.rest(1.0).unwrap().0, -65.0);
+
+        let mut restored = Equations::default();
+        restored.restore(&equations.to_json());
+        assert_eq!(restored, equations);
+    }
+}
@@ -0,0 +1,114 @@
+use serde_json::Value;
+
+/// Input ports driving `a`, `b`, `c`, `d`, in the order of
+/// [`crate::staging::PARAMS`].
+pub const INPUTS: [&str; 4] = ["mod_a", "mod_b", "mod_c", "mod_d"];
+
+/// How a modulation input acts on its parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ModulationMode {
+    /// The input is ignored.
+    #[default]
+    Off,
+    /// The parameter is multiplied by the input.
+    Scale,
+    /// The input replaces the parameter.
+    Override,
+}
+
+impl ModulationMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "off" | "none" => Some(Self::Off),
+            "scale" | "multiply" => Some(Self::Scale),
+            "override" | "replace" => Some(Self::Override),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Scale => "scale",
+            Self::Override => "override",
+        }
+    }
+}
+
+/// Per-parameter modulation inputs and their modes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Modulation {
+    pub modes: [ModulationMode; 4],
+    pub inputs: [f64; 4],
+}
+
+impl Modulation {
+    pub fn input_mut(&mut self, port: &str) -> Option<&mut f64> {
+        let k = INPUTS.iter().position(|&p| p == port)?;
+        Some(&mut self.inputs[k])
+    }
+
+    /// Handles the `mod_<p>_mode` keys; returns whether `key` was one of them.
+    pub fn set_mode(&mut self, key: &str, value: &Value) -> bool {
+        let Some(k) = key
+            .strip_suffix("_mode")
+            .and_then(|port| INPUTS.iter().position(|&p| p == port))
+        else {
+            return false;
+        };
+        if let Some(mode) = value.as_str().and_then(ModulationMode::from_name) {
+            self.modes[k] = mode;
+        }
+        true
+    }
+
+    /// The parameters as modulated by the latched inputs.
+    pub fn apply(&self, parameters: [f64; 4]) -> [f64; 4] {
+        let mut modulated = parameters;
+        for ((value, mode), x) in modulated.iter_mut().zip(self.modes).zip(self.inputs) {
+            match mode {
+                ModulationMode::Off => {}
+                ModulationMode::Scale => *value *= x,
+                ModulationMode::Override => *value = x,
+            }
+        }
+        modulated
+    }
+
+    pub fn mode_names(&self) -> Vec<&'static str> {
+        self.modes.iter().map(|mode| mode.name()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modes_scale_or_override_their_parameter() {
+        let mut modulation = Modulation::default();
+        for (key, mode) in [
+            ("mod_a_mode", "scale"),
+            ("mod_c_mode", "override"),
+            ("mod_d_mode", "bogus"),
+        ] {
+            assert!(modulation.set_mode(key, &Value::from(mode)));
+        }
+        assert!(!modulation.set_mode("a_mode", &Value::from("scale")));
+        assert_eq!(modulation.mode_names(), ["scale", "off", "override", "off"]);
+
+        modulation.inputs = [2.0, 3.0, -50.0, 4.0];
+        assert_eq!(
+            modulation.apply([0.02, 0.2, -65.0, 8.0]),
+            [0.04, 0.2, -50.0, 8.0]
+        );
+    }
+
+    #[test]
+    fn ports_map_to_their_parameters() {
+        let mut modulation = Modulation::default();
+        *modulation.input_mut("mod_b").unwrap() = 1.5;
+        assert_eq!(modulation.inputs, [0.0, 1.5, 0.0, 0.0]);
+        assert!(modulation.input_mut("mod_e").is_none());
+    }
+}
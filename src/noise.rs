@@ -0,0 +1,263 @@
+use serde_json::{json, Value};
+
+use crate::rng::Rng;
+use crate::snapshot::read;
+
+/// Current noise sources, each drawing from its own seeded generator so that
+/// enabling one does not change the realisation of the others.
+#[derive(Debug, Clone)]
+pub struct Noise {
+    /// White-noise intensity: `v` receives `sigma sqrt(dt) N(0, 1)` per
+    /// sub-step of `dt` ms.
+    pub white_sigma: f64,
+    white_seed: u64,
+    white_rng: Rng,
+    /// Ornstein-Uhlenbeck current with mean `ou_mean`, stationary standard
+    /// deviation `ou_sigma` and correlation time `ou_tau_ms`.
+    pub ou_mean: f64,
+    pub ou_sigma: f64,
+    pub ou_tau_ms: f64,
+    ou_seed: u64,
+    ou_rng: Rng,
+    ou: f64,
+    /// Poisson shot noise: events at `poisson_rate_hz`, each adding
+    /// `poisson_amp` to a current that decays with `poisson_tau_ms`.
+    pub poisson_rate_hz: f64,
+    pub poisson_amp: f64,
+    pub poisson_tau_ms: f64,
+    poisson_seed: u64,
+    poisson_rng: Rng,
+    shot: f64,
+}
+
+impl Default for Noise {
+    fn default() -> Self {
+        Self {
+            white_sigma: 0.0,
+            white_seed: 1,
+            white_rng: Rng::new(1),
+            ou_mean: 0.0,
+            ou_sigma: 0.0,
+            ou_tau_ms: 10.0,
+            ou_seed: 2,
+            ou_rng: Rng::new(2),
+            ou: 0.0,
+            poisson_rate_hz: 0.0,
+            poisson_amp: 1.0,
+            poisson_tau_ms: 5.0,
+            poisson_seed: 3,
+            poisson_rng: Rng::new(3),
+            shot: 0.0,
+        }
+    }
+}
+
+impl Noise {
+    /// Coloured noise current, held over a sub-step.
+    pub fn current(&self) -> f64 {
+        self.ou_mean + self.ou + self.shot
+    }
+
+    /// Advances the coloured sources by `dt_ms` and returns the white-noise
+    /// increment of `v` for the same step.
+    pub fn advance(&mut self, dt_ms: f64) -> f64 {
+        if self.ou_sigma != 0.0 && self.ou_tau_ms > 0.0 {
+            // Exact OU update.
+            let decay = (-dt_ms / self.ou_tau_ms).exp();
+            let spread = self.ou_sigma * (1.0 - decay * decay).sqrt();
+            self.ou = self.ou * decay + spread * self.ou_rng.normal();
+        }
+
+        if self.poisson_tau_ms > 0.0 {
+            self.shot *= (-dt_ms / self.poisson_tau_ms).exp();
+        }
+        if self.poisson_rate_hz > 0.0 {
+            let events = self
+                .poisson_rng
+                .poisson(self.poisson_rate_hz * dt_ms / 1000.0);
+            self.shot += self.poisson_amp * events as f64;
+        }
+
+        if self.white_sigma != 0.0 {
+            self.white_sigma * dt_ms.sqrt() * self.white_rng.normal()
+        } else {
+            0.0
+        }
+    }
+
+    /// Reseeds every generator and clears the coloured sources, so a restarted
+    /// run repeats the same realisation.
+    pub fn reset(&mut self) {
+        self.white_rng = Rng::new(self.white_seed);
+        self.ou_rng = Rng::new(self.ou_seed);
+        self.poisson_rng = Rng::new(self.poisson_seed);
+        self.ou = 0.0;
+        self.shot = 0.0;
+    }
+
+    /// Sets a `noise_*` config key; returns whether `key` was one of them.
+    pub fn set(&mut self, key: &str, x: f64) -> bool {
+        let seed = x.max(0.0) as u64;
+        match key {
+            "noise_white_sigma" => self.white_sigma = x,
+            "noise_white_seed" => {
+                self.white_seed = seed;
+                self.white_rng = Rng::new(seed);
+            }
+            "noise_ou_mean" => self.ou_mean = x,
+            "noise_ou_sigma" => self.ou_sigma = x,
+            "noise_ou_tau_ms" if x > 0.0 => self.ou_tau_ms = x,
+            "noise_ou_seed" => {
+                self.ou_seed = seed;
+                self.ou_rng = Rng::new(seed);
+            }
+            "noise_poisson_rate_hz" => self.poisson_rate_hz = x.max(0.0),
+            "noise_poisson_amp" => self.poisson_amp = x,
+            "noise_poisson_tau_ms" if x > 0.0 => self.poisson_tau_ms = x,
+            "noise_poisson_seed" => {
+                self.poisson_seed = seed;
+                self.poisson_rng = Rng::new(seed);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// The `noise_*` config keys and their current values.
+    pub fn values(&self) -> Vec<(&'static str, Value)> {
+        vec![
+            ("noise_white_sigma", self.white_sigma.into()),
+            ("noise_white_seed", self.white_seed.into()),
+            ("noise_ou_mean", self.ou_mean.into()),
+            ("noise_ou_sigma", self.ou_sigma.into()),
+            ("noise_ou_tau_ms", self.ou_tau_ms.into()),
+            ("noise_ou_seed", self.ou_seed.into()),
+            ("noise_poisson_rate_hz", self.poisson_rate_hz.into()),
+            ("noise_poisson_amp", self.poisson_amp.into()),
+            ("noise_poisson_tau_ms", self.poisson_tau_ms.into()),
+            ("noise_poisson_seed", self.poisson_seed.into()),
+        ]
+    }
+
+    pub fn default_vars() -> Vec<(&'static str, Value)> {
+        Self::default().values()
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut value: serde_json::Map<String, Value> = self
+            .values()
+            .into_iter()
+            .map(|(key, x)| (key.to_string(), x))
+            .collect();
+        value.insert("ou".into(), self.ou.into());
+        value.insert("shot".into(), self.shot.into());
+        value.insert(
+            "rng".into(),
+            json!({
+                "white": self.white_rng.to_json(),
+                "ou": self.ou_rng.to_json(),
+                "poisson": self.poisson_rng.to_json(),
+            }),
+        );
+        Value::Object(value)
+    }
+
+    /// Restores parameters, then the generator states saved after them.
+    pub fn restore(&mut self, value: &Value) {
+        for (key, _) in self.values() {
+            if let Some(x) = value.get(key).and_then(Value::as_f64) {
+                self.set(key, x);
+            }
+        }
+        read(value, "ou", &mut self.ou);
+        read(value, "shot", &mut self.shot);
+        let Some(rng) = value.get("rng") else {
+            return;
+        };
+        for (key, slot) in [
+            ("white", &mut self.white_rng),
+            ("ou", &mut self.ou_rng),
+            ("poisson", &mut self.poisson_rng),
+        ] {
+            if let Some(restored) = rng.get(key).and_then(Rng::from_json) {
+                *slot = restored;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noise(keys: &[(&str, f64)]) -> Noise {
+        let mut noise = Noise::default();
+        for &(key, x) in keys {
+            assert!(noise.set(key, x), "{key}");
+        }
+        noise
+    }
+
+    /// White-noise increments and coloured current over `n` 0.1 ms steps.
+    fn trace(noise: &mut Noise, n: usize) -> Vec<(f64, f64)> {
+        (0..n)
+            .map(|_| (noise.advance(0.1), noise.current()))
+            .collect()
+    }
+
+    #[test]
+    fn seeds_fix_the_realisation() {
+        let keys = [
+            ("noise_white_sigma", 1.0),
+            ("noise_ou_sigma", 1.0),
+            ("noise_poisson_rate_hz", 100.0),
+        ];
+        let first = trace(&mut noise(&keys), 1000);
+        assert_eq!(trace(&mut noise(&keys), 1000), first);
+
+        let mut reseeded = noise(&keys);
+        reseeded.set("noise_ou_seed", 7.0);
+        let other = trace(&mut reseeded, 1000);
+        assert_ne!(other, first);
+        // The other sources keep their realisation.
+        let white = |trace: &[(f64, f64)]| trace.iter().map(|s| s.0).collect::<Vec<_>>();
+        assert_eq!(white(&other), white(&first));
+
+        let mut restarted = noise(&keys);
+        trace(&mut restarted, 500);
+        restarted.reset();
+        assert_eq!(trace(&mut restarted, 1000), first);
+    }
+
+    #[test]
+    fn sources_do_not_disturb_each_other() {
+        let white_only = trace(&mut noise(&[("noise_white_sigma", 1.0)]), 1000);
+        let both = trace(
+            &mut noise(&[("noise_white_sigma", 1.0), ("noise_ou_sigma", 2.0)]),
+            1000,
+        );
+        assert!(white_only.iter().zip(&both).all(|(a, b)| a.0 == b.0));
+    }
+
+    #[test]
+    fn statistics_match_the_parameters() {
+        let n = 200_000;
+        let mut ou = noise(&[
+            ("noise_ou_mean", 3.0),
+            ("noise_ou_sigma", 2.0),
+            ("noise_ou_tau_ms", 1.0),
+        ]);
+        let samples: Vec<f64> = trace(&mut ou, n).iter().map(|s| s.1).collect();
+        let mean = samples.iter().sum::<f64>() / n as f64;
+        let sd = (samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n as f64).sqrt();
+        assert!(
+            (mean - 3.0).abs() < 0.1 && (sd - 2.0).abs() < 0.1,
+            "{mean} {sd}"
+        );
+
+        // Shot noise averages rate * amp * tau: 100 Hz * 0.5 * 5 ms.
+        let mut shot = noise(&[("noise_poisson_rate_hz", 100.0), ("noise_poisson_amp", 0.5)]);
+        let mean = trace(&mut shot, n).iter().map(|s| s.1).sum::<f64>() / n as f64;
+        assert!((mean - 0.25).abs() < 0.03, "{mean}");
+    }
+}
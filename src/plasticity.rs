@@ -0,0 +1,439 @@
+use serde_json::{json, Value};
+
+use crate::snapshot::read;
+use crate::synapse::{Synapse, MAX_INPUTS};
+
+/// Learning rule applied to the gains of the extendable inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StdpRule {
+    Off,
+    /// Pair-based STDP with all-to-all spike interactions.
+    Pair,
+    /// Minimal triplet rule of Pfister & Gerstner (2006): the pair terms are
+    /// boosted by a second, slower trace of the opposite side's spikes.
+    Triplet,
+}
+
+impl StdpRule {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "off" | "none" => Some(Self::Off),
+            "pair" | "stdp" => Some(Self::Pair),
+            "triplet" => Some(Self::Triplet),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Pair => "pair",
+            Self::Triplet => "triplet",
+        }
+    }
+}
+
+/// Spike-timing-dependent plasticity of the input gains, driven by the
+/// presynaptic spikes of the inputs and the neuron's resets.
+///
+/// With `dopamine_gated`, STDP only tags synapses: each change goes into an
+/// eligibility trace `e` that decays with `tau_e_ms`, and the weight follows
+/// `w' = e * DA`, where the dopamine level `DA` decays with `tau_da_ms`
+/// (Izhikevich 2007, distal reward problem).
+#[derive(Debug, Clone, Copy)]
+pub struct Stdp {
+    pub rule: StdpRule,
+    pub a2_plus: f64,
+    pub a2_minus: f64,
+    pub a3_plus: f64,
+    pub a3_minus: f64,
+    pub tau_plus_ms: f64,
+    pub tau_minus_ms: f64,
+    pub tau_x_ms: f64,
+    pub tau_y_ms: f64,
+    pub w_min: f64,
+    pub w_max: f64,
+    pub dopamine_gated: bool,
+    pub tau_e_ms: f64,
+    pub tau_da_ms: f64,
+    /// Value of the `Dopamine` port: a release rate (per ms) feeding the
+    /// dopamine level, or the level itself when `tau_da_ms <= 0`.
+    pub dopamine_input: f64,
+    dopamine: f64,
+    eligibility: [f64; MAX_INPUTS],
+    // Presynaptic traces (`tau_plus`, `tau_x`) per input and postsynaptic
+    // traces (`tau_minus`, `tau_y`).
+    r1: [f64; MAX_INPUTS],
+    r2: [f64; MAX_INPUTS],
+    o1: f64,
+    o2: f64,
+}
+
+impl Default for Stdp {
+    fn default() -> Self {
+        Self {
+            rule: StdpRule::Off,
+            a2_plus: 0.01,
+            a2_minus: 0.012,
+            a3_plus: 0.0062,
+            a3_minus: 0.00023,
+            tau_plus_ms: 16.8,
+            tau_minus_ms: 33.7,
+            tau_x_ms: 101.0,
+            tau_y_ms: 125.0,
+            w_min: 0.0,
+            w_max: 2.0,
+            dopamine_gated: false,
+            tau_e_ms: 1000.0,
+            tau_da_ms: 200.0,
+            dopamine_input: 0.0,
+            dopamine: 0.0,
+            eligibility: [0.0; MAX_INPUTS],
+            r1: [0.0; MAX_INPUTS],
+            r2: [0.0; MAX_INPUTS],
+            o1: 0.0,
+            o2: 0.0,
+        }
+    }
+}
+
+impl Stdp {
+    fn triplet(&self) -> f64 {
+        f64::from(u8::from(self.rule == StdpRule::Triplet))
+    }
+
+    // Not `clamp`, which panics when the bounds are set out of order.
+    fn bound(&self, w: f64) -> f64 {
+        w.max(self.w_min).min(self.w_max)
+    }
+
+    /// Applies an STDP change, or tags the synapse with it when gated.
+    fn update(&mut self, k: usize, synapse: &mut Synapse, dw: f64) {
+        if self.dopamine_gated {
+            self.eligibility[k] += dw;
+        } else {
+            synapse.gain = self.bound(synapse.gain + dw);
+        }
+    }
+
+    pub fn dopamine(&self) -> f64 {
+        self.dopamine
+    }
+
+    pub fn eligibility(&self, k: usize) -> Option<f64> {
+        self.eligibility.get(k).copied()
+    }
+
+    /// Takes in the `Dopamine` port at the start of a tick when it is the
+    /// level itself; as a rate it is integrated by [`Self::advance`].
+    pub fn latch_dopamine(&mut self) {
+        if self.tau_da_ms <= 0.0 {
+            self.dopamine = self.dopamine_input;
+        }
+    }
+
+    /// Presynaptic spikes, taken at the start of a tick: depression by the
+    /// postsynaptic trace.
+    pub fn on_pre(&mut self, synapses: &mut [Synapse; MAX_INPUTS], spiked: [bool; MAX_INPUTS]) {
+        if self.rule == StdpRule::Off {
+            return;
+        }
+        let triplet = self.triplet();
+        for (k, synapse) in synapses.iter_mut().enumerate() {
+            if !spiked[k] {
+                continue;
+            }
+            let dw = -self.o1 * (self.a2_minus + triplet * self.a3_minus * self.r2[k]);
+            self.update(k, synapse, dw);
+            self.r1[k] += 1.0;
+            self.r2[k] += 1.0;
+        }
+    }
+
+    /// A reset of the neuron: potentiation by every presynaptic trace.
+    pub fn on_post(&mut self, synapses: &mut [Synapse; MAX_INPUTS]) {
+        if self.rule == StdpRule::Off {
+            return;
+        }
+        let triplet = self.triplet();
+        for (k, synapse) in synapses.iter_mut().enumerate() {
+            let dw = self.r1[k] * (self.a2_plus + triplet * self.a3_plus * self.o2);
+            self.update(k, synapse, dw);
+        }
+        self.o1 += 1.0;
+        self.o2 += 1.0;
+    }
+
+    /// Decays the traces over `dt_ms` and, when gated, moves the weights by
+    /// `e * DA * dt` and updates `DA' = -DA / tau_da + release rate`.
+    pub fn advance(&mut self, synapses: &mut [Synapse; MAX_INPUTS], dt_ms: f64) {
+        if self.rule == StdpRule::Off {
+            return;
+        }
+        if self.dopamine_gated {
+            for (synapse, e) in synapses.iter_mut().zip(&self.eligibility) {
+                synapse.gain = self.bound(synapse.gain + e * self.dopamine * dt_ms);
+            }
+            let decay = (-dt_ms / self.tau_e_ms).exp();
+            self.eligibility.iter_mut().for_each(|e| *e *= decay);
+            if self.tau_da_ms > 0.0 {
+                // Exact solution over the step, so the level does not depend
+                // on the host's tick period.
+                let decay = (-dt_ms / self.tau_da_ms).exp();
+                self.dopamine =
+                    self.dopamine * decay + self.dopamine_input * self.tau_da_ms * (1.0 - decay);
+            }
+        }
+        let (r1, r2) = (
+            (-dt_ms / self.tau_plus_ms).exp(),
+            (-dt_ms / self.tau_x_ms).exp(),
+        );
+        self.r1.iter_mut().for_each(|r| *r *= r1);
+        self.r2.iter_mut().for_each(|r| *r *= r2);
+        self.o1 *= (-dt_ms / self.tau_minus_ms).exp();
+        self.o2 *= (-dt_ms / self.tau_y_ms).exp();
+    }
+
+    /// Clears the spike, eligibility and dopamine traces; the learned gains are left alone.
+    pub fn reset(&mut self) {
+        self.r1 = [0.0; MAX_INPUTS];
+        self.r2 = [0.0; MAX_INPUTS];
+        self.o1 = 0.0;
+        self.o2 = 0.0;
+        self.eligibility = [0.0; MAX_INPUTS];
+        self.dopamine = 0.0;
+    }
+
+    /// Sets a numeric `stdp_*` config key; returns whether `key` was one of
+    /// them.
+    pub fn set(&mut self, key: &str, x: f64) -> bool {
+        match key {
+            "stdp_a2_plus" => self.a2_plus = x,
+            "stdp_a2_minus" => self.a2_minus = x,
+            "stdp_a3_plus" => self.a3_plus = x,
+            "stdp_a3_minus" => self.a3_minus = x,
+            "stdp_tau_plus_ms" if x > 0.0 => self.tau_plus_ms = x,
+            "stdp_tau_minus_ms" if x > 0.0 => self.tau_minus_ms = x,
+            "stdp_tau_x_ms" if x > 0.0 => self.tau_x_ms = x,
+            "stdp_tau_y_ms" if x > 0.0 => self.tau_y_ms = x,
+            "stdp_w_min" => self.w_min = x,
+            "stdp_w_max" => self.w_max = x,
+            "stdp_dopamine" => self.dopamine_gated = x != 0.0,
+            "stdp_tau_e_ms" if x > 0.0 => self.tau_e_ms = x,
+            "stdp_tau_da_ms" => self.tau_da_ms = x,
+            _ => return false,
+        }
+        true
+    }
+
+    /// The `stdp_*` config keys and their current values.
+    pub fn values(&self) -> Vec<(&'static str, Value)> {
+        vec![
+            ("stdp_rule", self.rule.name().into()),
+            ("stdp_a2_plus", self.a2_plus.into()),
+            ("stdp_a2_minus", self.a2_minus.into()),
+            ("stdp_a3_plus", self.a3_plus.into()),
+            ("stdp_a3_minus", self.a3_minus.into()),
+            ("stdp_tau_plus_ms", self.tau_plus_ms.into()),
+            ("stdp_tau_minus_ms", self.tau_minus_ms.into()),
+            ("stdp_tau_x_ms", self.tau_x_ms.into()),
+            ("stdp_tau_y_ms", self.tau_y_ms.into()),
+            ("stdp_w_min", self.w_min.into()),
+            ("stdp_w_max", self.w_max.into()),
+            ("stdp_dopamine", self.dopamine_gated.into()),
+            ("stdp_tau_e_ms", self.tau_e_ms.into()),
+            ("stdp_tau_da_ms", self.tau_da_ms.into()),
+        ]
+    }
+
+    pub fn default_vars() -> Vec<(&'static str, Value)> {
+        Self::default().values()
+    }
+
+    pub fn to_json(self) -> Value {
+        let mut value: serde_json::Map<String, Value> = self
+            .values()
+            .into_iter()
+            .map(|(key, x)| (key.to_string(), x))
+            .collect();
+        value.insert(
+            "traces".into(),
+            json!({
+                "r1": self.r1,
+                "r2": self.r2,
+                "o1": self.o1,
+                "o2": self.o2,
+                "eligibility": self.eligibility,
+                "dopamine": self.dopamine,
+            }),
+        );
+        Value::Object(value)
+    }
+
+    pub fn restore(&mut self, value: &Value) {
+        if let Some(rule) = value
+            .get("stdp_rule")
+            .and_then(Value::as_str)
+            .and_then(StdpRule::from_name)
+        {
+            self.rule = rule;
+        }
+        for (key, _) in self.values() {
+            if let Some(x) = value.get(key).and_then(Value::as_f64) {
+                self.set(key, x);
+            }
+        }
+        if let Some(gated) = value.get("stdp_dopamine").and_then(Value::as_bool) {
+            self.dopamine_gated = gated;
+        }
+        let Some(traces) = value.get("traces") else {
+            return;
+        };
+        for (key, slots) in [
+            ("r1", &mut self.r1),
+            ("r2", &mut self.r2),
+            ("eligibility", &mut self.eligibility),
+        ] {
+            if let Some(saved) = traces.get(key).and_then(Value::as_array) {
+                for (slot, x) in slots.iter_mut().zip(saved) {
+                    *slot = x.as_f64().unwrap_or(*slot);
+                }
+            }
+        }
+        read(traces, "o1", &mut self.o1);
+        read(traces, "o2", &mut self.o2);
+        read(traces, "dopamine", &mut self.dopamine);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stdp(rule: StdpRule) -> Stdp {
+        Stdp {
+            rule,
+            ..Stdp::default()
+        }
+    }
+
+    fn spike_on(k: usize) -> [bool; MAX_INPUTS] {
+        let mut spiked = [false; MAX_INPUTS];
+        spiked[k] = true;
+        spiked
+    }
+
+    /// Weight change of input 0 after spike events at the given times (ms):
+    /// `true` for presynaptic, `false` for postsynaptic.
+    fn weight_change(stdp: &mut Stdp, events: &[(f64, bool)]) -> f64 {
+        let mut synapses = [Synapse::default(); MAX_INPUTS];
+        let mut t_ms = 0.0;
+        for &(at_ms, pre) in events {
+            stdp.advance(&mut synapses, at_ms - t_ms);
+            t_ms = at_ms;
+            if pre {
+                stdp.on_pre(&mut synapses, spike_on(0));
+            } else {
+                stdp.on_post(&mut synapses);
+            }
+        }
+        synapses[0].gain - 1.0
+    }
+
+    #[test]
+    fn pair_rule_follows_the_stdp_window() {
+        let defaults = Stdp::default();
+        for dt in [5.0, 20.0] {
+            let ltp = weight_change(&mut stdp(StdpRule::Pair), &[(0.0, true), (dt, false)]);
+            let ltd = weight_change(&mut stdp(StdpRule::Pair), &[(0.0, false), (dt, true)]);
+            let expected_ltp = defaults.a2_plus * (-dt / defaults.tau_plus_ms).exp();
+            let expected_ltd = -defaults.a2_minus * (-dt / defaults.tau_minus_ms).exp();
+            assert!((ltp - expected_ltp).abs() < 1e-12, "{ltp}");
+            assert!((ltd - expected_ltd).abs() < 1e-12, "{ltd}");
+        }
+        assert_eq!(
+            weight_change(&mut stdp(StdpRule::Off), &[(0.0, true), (5.0, false)]),
+            0.0
+        );
+    }
+
+    #[test]
+    fn triplets_boost_potentiation_after_a_postsynaptic_spike() {
+        // post-pre-post: the second pairing sees the first post spike in `o2`.
+        let events = [(0.0, false), (10.0, true), (15.0, false)];
+        let pair = weight_change(&mut stdp(StdpRule::Pair), &events);
+        let triplet = weight_change(&mut stdp(StdpRule::Triplet), &events);
+        let defaults = Stdp::default();
+        let o2 = (-15.0 / defaults.tau_y_ms).exp();
+        let r1 = (-5.0 / defaults.tau_plus_ms).exp();
+        assert!((triplet - pair - defaults.a3_plus * o2 * r1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn weights_stay_within_bounds_and_reset_keeps_them() {
+        let mut rule = Stdp {
+            a2_plus: 1.0,
+            ..stdp(StdpRule::Pair)
+        };
+        let mut synapses = [Synapse::default(); MAX_INPUTS];
+        for _ in 0..10 {
+            rule.on_pre(&mut synapses, spike_on(0));
+            rule.on_post(&mut synapses);
+        }
+        assert_eq!(synapses[0].gain, rule.w_max);
+        // Inputs that never spiked keep their weight.
+        assert_eq!(synapses[1].gain, 1.0);
+
+        rule.reset();
+        rule.on_post(&mut synapses);
+        assert_eq!(synapses[0].gain, rule.w_max);
+    }
+
+    /// Dopamine-gated STDP with input 0 tagged by `e`, after `ms` of ticks
+    /// of `period_ms` with the `Dopamine` port at `rate`.
+    fn gated(e: f64, rate: f64, period_ms: f64, ms: f64) -> (Stdp, f64) {
+        let mut stdp = Stdp {
+            dopamine_gated: true,
+            dopamine_input: rate,
+            ..stdp(StdpRule::Pair)
+        };
+        stdp.eligibility[0] = e;
+        let mut synapses = [Synapse::default(); MAX_INPUTS];
+        for _ in 0..(ms / period_ms).round() as usize {
+            stdp.latch_dopamine();
+            stdp.advance(&mut synapses, period_ms);
+        }
+        (stdp, synapses[0].gain)
+    }
+
+    #[test]
+    fn dopamine_level_does_not_depend_on_the_tick_period() {
+        let (fast, w_fast) = gated(0.0, 0.01, 0.1, 100.0);
+        let (slow, w_slow) = gated(0.0, 0.01, 1.0, 100.0);
+        let expected = 0.01 * 200.0 * (1.0 - (-100.0f64 / 200.0).exp());
+        assert!((fast.dopamine() - expected).abs() < 1e-12);
+        assert!((slow.dopamine() - expected).abs() < 1e-12);
+        // Untagged synapses do not learn.
+        assert_eq!((w_fast, w_slow), (1.0, 1.0));
+
+        let mut level = Stdp {
+            tau_da_ms: 0.0,
+            dopamine_input: 0.3,
+            ..Stdp::default()
+        };
+        level.latch_dopamine();
+        assert_eq!(level.dopamine(), 0.3);
+    }
+
+    #[test]
+    fn dopamine_consolidates_tagged_synapses() {
+        // Without dopamine the tag decays away unused.
+        let (untouched, w) = gated(0.1, 0.0, 0.1, 100.0);
+        assert_eq!(w, 1.0);
+        assert!((untouched.eligibility(0).unwrap() - 0.1 * (-0.1f64).exp()).abs() < 1e-12);
+        let (_, w) = gated(0.1, 0.01, 0.1, 100.0);
+        assert!(w > 1.0);
+        let (_, w) = gated(-0.1, 0.01, 0.1, 100.0);
+        assert!(w < 1.0);
+    }
+}
@@ -0,0 +1,560 @@
+use crate::integrator::Integrator;
+use crate::model::{Equations, Recovery};
+use crate::stimulus::{hold, ramp, Protocol};
+
+/// Canonical parameter set for a named neuron class or behaviour.
+#[derive(Debug, Clone, Copy)]
+pub struct Preset {
+    pub names: &'static [&'static str],
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub v0: f64,
+    /// Initial recovery variable; `None` puts it on the nullcline, `b * v0`.
+    pub u0: Option<f64>,
+    pub equations: Equations,
+    /// Scheme and step the behaviour was published with; `None` keeps the
+    /// configured ones.
+    pub integrator: Option<Integrator>,
+    pub step_ms: Option<f64>,
+    /// Stimulus protocol replayed from the moment the preset is loaded.
+    pub protocol: Option<&'static Protocol>,
+}
+
+impl Preset {
+    pub fn u0(&self) -> f64 {
+        self.u0.unwrap_or(self.b * self.v0)
+    }
+}
+
+const fn preset(names: &'static [&'static str], a: f64, b: f64, c: f64, d: f64) -> Preset {
+    Preset {
+        names,
+        a,
+        b,
+        c,
+        d,
+        v0: -65.0,
+        u0: None,
+        equations: Equations::STANDARD,
+        integrator: None,
+        step_ms: None,
+        protocol: None,
+    }
+}
+
+/// Neuron classes of Fig. 2 in Izhikevich (2003).
+pub const NEURON_CLASSES: &[Preset] = &[
+    preset(&["rs", "regular_spiking"], 0.02, 0.2, -65.0, 8.0),
+    preset(&["ib", "intrinsically_bursting"], 0.02, 0.2, -55.0, 4.0),
+    preset(&["ch", "chattering"], 0.02, 0.2, -50.0, 2.0),
+    preset(&["fs", "fast_spiking"], 0.1, 0.2, -65.0, 2.0),
+    preset(&["lts", "low_threshold_spiking"], 0.02, 0.25, -65.0, 2.0),
+    preset(&["tc", "thalamo_cortical"], 0.02, 0.25, -65.0, 0.05),
+    preset(&["rz", "resonator"], 0.1, 0.26, -65.0, 2.0),
+];
+
+#[allow(clippy::too_many_arguments)]
+const fn feature(
+    names: &'static [&'static str],
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    v0: f64,
+    step_ms: f64,
+    protocol: &'static Protocol,
+) -> Preset {
+    Preset {
+        v0,
+        integrator: Some(Integrator::SemiImplicitEuler),
+        step_ms: Some(step_ms),
+        protocol: Some(protocol),
+        ..preset(names, a, b, c, d)
+    }
+}
+
+const CLASS_1: Equations = Equations {
+    k1: 4.1,
+    k0: 108.0,
+    ..Equations::STANDARD
+};
+
+/// The 20 neuro-computational features of Fig. 1 in Izhikevich (2004), with
+/// the stimulus protocols of the accompanying MATLAB code. Names carry the
+/// panel letter; `resonator` alone selects the 2003 RZ class.
+pub const FEATURES: &[Preset] = &[
+    feature(
+        &["a_tonic_spiking", "tonic_spiking"],
+        0.02,
+        0.2,
+        -65.0,
+        6.0,
+        -70.0,
+        0.25,
+        &Protocol {
+            baseline: 0.0,
+            segments: &[hold(10.0, f64::INFINITY, 14.0)],
+        },
+    ),
+    feature(
+        &["b_phasic_spiking", "phasic_spiking"],
+        0.02,
+        0.25,
+        -65.0,
+        6.0,
+        -64.0,
+        0.25,
+        &Protocol {
+            baseline: 0.0,
+            segments: &[hold(20.0, f64::INFINITY, 0.5)],
+        },
+    ),
+    feature(
+        &["c_tonic_bursting", "tonic_bursting"],
+        0.02,
+        0.2,
+        -50.0,
+        2.0,
+        -70.0,
+        0.25,
+        &Protocol {
+            baseline: 0.0,
+            segments: &[hold(22.0, f64::INFINITY, 15.0)],
+        },
+    ),
+    feature(
+        &["d_phasic_bursting", "phasic_bursting"],
+        0.02,
+        0.25,
+        -55.0,
+        0.05,
+        -64.0,
+        0.2,
+        &Protocol {
+            baseline: 0.0,
+            segments: &[hold(20.0, f64::INFINITY, 0.6)],
+        },
+    ),
+    feature(
+        &["e_mixed_mode", "mixed_mode"],
+        0.02,
+        0.2,
+        -55.0,
+        4.0,
+        -70.0,
+        0.25,
+        &Protocol {
+            baseline: 0.0,
+            segments: &[hold(16.0, f64::INFINITY, 10.0)],
+        },
+    ),
+    feature(
+        &["f_spike_frequency_adaptation", "spike_frequency_adaptation"],
+        0.01,
+        0.2,
+        -65.0,
+        8.0,
+        -70.0,
+        0.25,
+        &Protocol {
+            baseline: 0.0,
+            segments: &[hold(8.5, f64::INFINITY, 30.0)],
+        },
+    ),
+    Preset {
+        equations: CLASS_1,
+        ..feature(
+            &["g_class_1_excitable", "class_1_excitable", "class_1"],
+            0.02,
+            -0.1,
+            -55.0,
+            6.0,
+            -60.0,
+            0.25,
+            &Protocol {
+                baseline: 0.0,
+                segments: &[ramp(30.0, f64::INFINITY, 0.0, 0.075)],
+            },
+        )
+    },
+    feature(
+        &["h_class_2_excitable", "class_2_excitable", "class_2"],
+        0.2,
+        0.26,
+        -65.0,
+        0.0,
+        -64.0,
+        0.25,
+        &Protocol {
+            baseline: -0.5,
+            segments: &[ramp(30.0, f64::INFINITY, -0.5, 0.015)],
+        },
+    ),
+    feature(
+        &["i_spike_latency", "spike_latency"],
+        0.02,
+        0.2,
+        -65.0,
+        6.0,
+        -70.0,
+        0.2,
+        &Protocol {
+            baseline: 0.0,
+            segments: &[hold(10.0, 13.0, 7.04)],
+        },
+    ),
+    feature(
+        &["j_subthreshold_oscillations", "subthreshold_oscillations"],
+        0.05,
+        0.26,
+        -60.0,
+        0.0,
+        -62.0,
+        0.25,
+        &Protocol {
+            baseline: 0.0,
+            segments: &[hold(20.0, 25.0, 2.0)],
+        },
+    ),
+    feature(
+        &["k_resonator", "resonator"],
+        0.1,
+        0.26,
+        -60.0,
+        -1.0,
+        -62.0,
+        0.25,
+        &Protocol {
+            baseline: 0.0,
+            segments: &[
+                hold(40.0, 44.0, 0.65),
+                hold(60.0, 64.0, 0.65),
+                hold(280.0, 284.0, 0.65),
+                hold(320.0, 324.0, 0.65),
+            ],
+        },
+    ),
+    Preset {
+        equations: CLASS_1,
+        ..feature(
+            &["l_integrator", "integrator"],
+            0.02,
+            -0.1,
+            -55.0,
+            6.0,
+            -60.0,
+            0.25,
+            &Protocol {
+                baseline: 0.0,
+                segments: &[
+                    hold(100.0 / 11.0, 100.0 / 11.0 + 2.0, 9.0),
+                    hold(100.0 / 11.0 + 5.0, 100.0 / 11.0 + 7.0, 9.0),
+                    hold(70.0, 72.0, 9.0),
+                    hold(80.0, 82.0, 9.0),
+                ],
+            },
+        )
+    },
+    feature(
+        &["m_rebound_spike", "rebound_spike"],
+        0.03,
+        0.25,
+        -60.0,
+        4.0,
+        -64.0,
+        0.2,
+        &Protocol {
+            baseline: 0.0,
+            segments: &[hold(20.0, 25.0, -15.0)],
+        },
+    ),
+    feature(
+        &["n_rebound_burst", "rebound_burst"],
+        0.03,
+        0.25,
+        -52.0,
+        0.0,
+        -64.0,
+        0.2,
+        &Protocol {
+            baseline: 0.0,
+            segments: &[hold(20.0, 25.0, -15.0)],
+        },
+    ),
+    feature(
+        &["o_threshold_variability", "threshold_variability"],
+        0.03,
+        0.25,
+        -60.0,
+        4.0,
+        -64.0,
+        0.25,
+        &Protocol {
+            baseline: 0.0,
+            segments: &[
+                hold(10.0, 15.0, 1.0),
+                hold(70.0, 75.0, -6.0),
+                hold(80.0, 85.0, 1.0),
+            ],
+        },
+    ),
+    feature(
+        &["p_bistability", "bistability"],
+        0.1,
+        0.26,
+        -60.0,
+        0.0,
+        -61.0,
+        0.25,
+        &Protocol {
+            baseline: 0.24,
+            segments: &[hold(37.5, 42.5, 1.24), hold(216.0, 221.0, 1.24)],
+        },
+    ),
+    feature(
+        &[
+            "q_depolarizing_after_potential",
+            "depolarizing_after_potential",
+            "dap",
+        ],
+        1.0,
+        0.2,
+        -60.0,
+        -21.0,
+        -70.0,
+        0.1,
+        &Protocol {
+            baseline: 0.0,
+            segments: &[hold(9.0, 11.0, 20.0)],
+        },
+    ),
+    Preset {
+        u0: Some(-16.0),
+        equations: Equations {
+            recovery: Recovery::Accommodation,
+            ..Equations::STANDARD
+        },
+        ..feature(
+            &["r_accommodation", "accommodation"],
+            0.02,
+            1.0,
+            -55.0,
+            4.0,
+            -65.0,
+            0.5,
+            &Protocol {
+                baseline: 0.0,
+                segments: &[
+                    ramp(0.0, 200.0, 0.0, 1.0 / 25.0),
+                    ramp(300.0, 312.5, 0.0, 4.0 / 12.5),
+                ],
+            },
+        )
+    },
+    feature(
+        &["s_inhibition_induced_spiking", "inhibition_induced_spiking"],
+        -0.02,
+        -1.0,
+        -60.0,
+        8.0,
+        -63.8,
+        0.5,
+        &Protocol {
+            baseline: 80.0,
+            segments: &[hold(50.0, 250.0, 75.0)],
+        },
+    ),
+    feature(
+        &[
+            "t_inhibition_induced_bursting",
+            "inhibition_induced_bursting",
+        ],
+        -0.026,
+        -1.0,
+        -45.0,
+        -2.0,
+        -63.8,
+        0.5,
+        &Protocol {
+            baseline: 80.0,
+            segments: &[hold(50.0, 250.0, 75.0)],
+        },
+    ),
+];
+
+/// Looks up a preset by any of its names, ignoring case, spaces and dashes.
+/// The 2003 neuron classes take precedence over the 2004 features.
+pub fn find(name: &str) -> Option<&'static Preset> {
+    let name = name.trim().to_ascii_lowercase().replace([' ', '-'], "_");
+    NEURON_CLASSES
+        .iter()
+        .chain(FEATURES)
+        .find(|preset| preset.names.contains(&name.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use rtsyn_plugin::prelude::*;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::Izhikevich2003Neuron;
+
+    /// Spike times (ms) of `name` over `duration_ms`, ticking at the preset's
+    /// own step, with `i_syn` held at `i`.
+    fn spike_times(name: &str, duration_ms: f64, i: f64) -> Vec<f64> {
+        let preset = find(name).unwrap();
+        let step_ms = preset.step_ms.unwrap_or(0.5);
+        let mut neuron = Izhikevich2003Neuron::default();
+        neuron.set_config_value("preset", &Value::from(name));
+        neuron.set_input_value("i_syn", i);
+        let mut times = Vec::new();
+        for tick in 0..(duration_ms / step_ms).round() as u64 {
+            neuron.process_tick(tick, step_ms / 1000.0);
+            if neuron.spikes_this_tick > 0 {
+                times.push(neuron.last_spike_s.unwrap() * 1000.0);
+            }
+        }
+        times
+    }
+
+    /// Groups spikes closer than `gap_ms` into bursts.
+    fn bursts(times: &[f64], gap_ms: f64) -> Vec<Vec<f64>> {
+        let mut bursts: Vec<Vec<f64>> = Vec::new();
+        for &t in times {
+            match bursts.last_mut() {
+                Some(burst) if t - burst.last().unwrap() < gap_ms => burst.push(t),
+                _ => bursts.push(vec![t]),
+            }
+        }
+        bursts
+    }
+
+    fn isis(times: &[f64]) -> Vec<f64> {
+        times.windows(2).map(|w| w[1] - w[0]).collect()
+    }
+
+    #[test]
+    fn names_are_found_loosely_and_classes_win() {
+        assert_eq!(find("Fast-Spiking").unwrap().names[0], "fs");
+        assert_eq!(find("T inhibition induced bursting").unwrap().a, -0.026);
+        assert_eq!(find("resonator").unwrap().names[0], "rz");
+        assert!(find("k_resonator").unwrap().protocol.is_some());
+        assert!(find("no_such_class").is_none());
+    }
+
+    #[test]
+    fn neuron_classes_fire_their_fig_2_patterns() {
+        let rs = spike_times("rs", 300.0, 10.0);
+        let isi = isis(&rs);
+        assert!(isi[0] > 10.0 && isi[0] < isi[isi.len() - 1], "rs {rs:?}");
+
+        let ib = bursts(&spike_times("ib", 300.0, 10.0), 15.0);
+        assert!(
+            ib[0].len() >= 2 && ib[1..].iter().all(|b| b.len() == 1),
+            "ib {ib:?}"
+        );
+
+        let ch = bursts(&spike_times("ch", 300.0, 10.0), 10.0);
+        assert!(
+            ch.len() >= 3 && ch.iter().all(|b| b.len() >= 3),
+            "ch {ch:?}"
+        );
+
+        let fs = spike_times("fs", 300.0, 10.0);
+        let isi = isis(&fs);
+        assert!(
+            fs.len() > 25 && isi[isi.len() - 1] < 2.0 * isi[1],
+            "fs {fs:?}"
+        );
+
+        let lts = isis(&spike_times("lts", 300.0, 10.0));
+        assert!(lts[0] < 5.0 && lts[lts.len() - 1] > 10.0, "lts {lts:?}");
+
+        for name in ["tc", "rz"] {
+            assert!(spike_times(name, 300.0, 10.0).len() > 20, "{name}");
+        }
+    }
+
+    #[test]
+    fn features_fire_their_fig_1_patterns() {
+        let tonic = spike_times("a_tonic_spiking", 400.0, 0.0);
+        assert!(tonic.len() >= 10 && tonic[0] > 10.0);
+
+        assert_eq!(spike_times("b_phasic_spiking", 400.0, 0.0).len(), 1);
+
+        let tonic_bursts = bursts(&spike_times("c_tonic_bursting", 400.0, 0.0), 20.0);
+        // The last burst may be cut short by the end of the run.
+        let complete = &tonic_bursts[..tonic_bursts.len() - 1];
+        assert!(complete.len() >= 5 && complete.iter().all(|b| b.len() >= 3));
+
+        let phasic_bursts = bursts(&spike_times("d_phasic_bursting", 400.0, 0.0), 20.0);
+        assert!(phasic_bursts.len() == 1 && phasic_bursts[0].len() >= 3);
+
+        let mixed = bursts(&spike_times("e_mixed_mode", 400.0, 0.0), 10.0);
+        assert!(mixed[0].len() >= 2 && mixed[1..].iter().all(|b| b.len() == 1));
+
+        let adapting = isis(&spike_times("f_spike_frequency_adaptation", 400.0, 0.0));
+        assert!(adapting[0] * 5.0 < adapting[adapting.len() - 1]);
+
+        // Class 1 starts at an arbitrarily low rate, class 2 at a high one.
+        let class_1 = isis(&spike_times("g_class_1_excitable", 400.0, 0.0));
+        assert!(class_1[0] > 30.0 && class_1[class_1.len() - 1] < class_1[0] / 2.0);
+        let class_2 = isis(&spike_times("h_class_2_excitable", 400.0, 0.0));
+        assert!(class_2[0] < 25.0);
+
+        let latency = spike_times("i_spike_latency", 100.0, 0.0);
+        assert!(latency.len() == 1 && latency[0] > 13.0);
+
+        assert_eq!(
+            spike_times("j_subthreshold_oscillations", 200.0, 0.0).len(),
+            1
+        );
+
+        let resonator = spike_times("k_resonator", 400.0, 0.0);
+        assert!(
+            resonator.len() == 1 && resonator[0] > 280.0,
+            "{resonator:?}"
+        );
+
+        let integrator = spike_times("l_integrator", 100.0, 0.0);
+        assert!(
+            integrator.len() == 1 && integrator[0] < 30.0,
+            "{integrator:?}"
+        );
+
+        let rebound = spike_times("m_rebound_spike", 200.0, 0.0);
+        assert!(rebound.len() == 1 && rebound[0] > 25.0);
+
+        let rebound_burst = bursts(&spike_times("n_rebound_burst", 200.0, 0.0), 10.0);
+        assert!(rebound_burst.len() == 1 && rebound_burst[0].len() >= 3);
+        assert!(rebound_burst[0][0] > 25.0);
+
+        let variability = spike_times("o_threshold_variability", 100.0, 0.0);
+        assert!(variability.len() == 1 && variability[0] > 80.0);
+
+        let bistable = spike_times("p_bistability", 300.0, 0.0);
+        assert!(bistable.len() >= 3);
+        assert!(bistable.iter().all(|&t| (37.5..221.0).contains(&t)));
+
+        let dap = spike_times("q_depolarizing_after_potential", 50.0, 0.0);
+        assert!(dap.len() == 1 && (9.0..15.0).contains(&dap[0]));
+
+        let accommodation = spike_times("r_accommodation", 400.0, 0.0);
+        assert!(accommodation.len() == 1 && (300.0..312.5).contains(&accommodation[0]));
+
+        let inhibition = spike_times("s_inhibition_induced_spiking", 400.0, 0.0);
+        assert!(inhibition.len() >= 2);
+        assert!(inhibition.iter().all(|&t| (50.0..250.0).contains(&t)));
+
+        let inhibition_bursts = bursts(
+            &spike_times("t_inhibition_induced_bursting", 400.0, 0.0),
+            20.0,
+        );
+        assert!(inhibition_bursts.len() >= 2);
+        assert!(inhibition_bursts.iter().all(|b| b.len() >= 3));
+        assert_eq!(inhibition_bursts.concat().len(), 12);
+    }
+}
@@ -0,0 +1,96 @@
+use serde_json::{json, Value};
+
+/// xoshiro256++ seeded through SplitMix64: small, fast and reproducible
+/// across platforms for a given seed.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    s: [u64; 4],
+    spare_normal: Option<f64>,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let mut x = seed;
+        let mut splitmix = || {
+            x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = x;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        };
+        Self {
+            s: [splitmix(), splitmix(), splitmix(), splitmix()],
+            spare_normal: None,
+        }
+    }
+
+    /// Generator state, so a run can be resumed mid-sequence.
+    pub fn to_json(&self) -> Value {
+        json!({ "s": self.s, "spare_normal": self.spare_normal })
+    }
+
+    pub fn from_json(value: &Value) -> Option<Self> {
+        let words = value.get("s")?.as_array()?;
+        let mut s = [0; 4];
+        if words.len() != s.len() {
+            return None;
+        }
+        for (slot, word) in s.iter_mut().zip(words) {
+            *slot = word.as_u64()?;
+        }
+        Some(Self {
+            s,
+            spare_normal: value.get("spare_normal").and_then(Value::as_f64),
+        })
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.s;
+        let result = s[0].wrapping_add(s[3]).rotate_left(23).wrapping_add(s[0]);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// Uniform sample in `[0, 1)`.
+    pub fn uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    /// Standard normal sample (Box-Muller, caching the second value).
+    pub fn normal(&mut self) -> f64 {
+        if let Some(z) = self.spare_normal.take() {
+            return z;
+        }
+        let u1 = 1.0 - self.uniform();
+        let u2 = self.uniform();
+        let r = (-2.0 * u1.ln()).sqrt();
+        let theta = std::f64::consts::TAU * u2;
+        self.spare_normal = Some(r * theta.sin());
+        r * theta.cos()
+    }
+
+    /// Poisson sample with the given mean (Knuth for small means, rounded
+    /// normal approximation above 30).
+    pub fn poisson(&mut self, mean: f64) -> u64 {
+        if mean <= 0.0 {
+            return 0;
+        }
+        if mean > 30.0 {
+            return (mean + mean.sqrt() * self.normal()).round().max(0.0) as u64;
+        }
+        let limit = (-mean).exp();
+        let mut k = 0;
+        let mut p = self.uniform();
+        while p > limit {
+            k += 1;
+            p *= self.uniform();
+        }
+        k
+    }
+}
@@ -0,0 +1,385 @@
+//! Versioned JSON snapshot of the full neuron state, written by `save_state`
+//! and read back by `load_state`.
+
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+use serde_json::{json, Value};
+
+use crate::display::SpikeDisplay;
+use crate::modulation;
+use crate::presets::{self, FEATURES};
+use crate::stp::Stp;
+use crate::{Izhikevich2003Neuron, RestartMode};
+
+pub const SNAPSHOT_VERSION: u64 = 1;
+const KIND: &str = "izhikevich_2003_neuron";
+
+/// Overwrites `slot` with the finite number stored under `key`, if any.
+pub(crate) fn read(value: &Value, key: &str, slot: &mut f64) {
+    if let Some(x) = value
+        .get(key)
+        .and_then(Value::as_f64)
+        .filter(|x| x.is_finite())
+    {
+        *slot = x;
+    }
+}
+
+/// Outcome of the last `save_state` or `load_state`, shown as the
+/// `state_status` internal variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StateStatus {
+    Idle,
+    Pending,
+    Done,
+    Failed,
+}
+
+impl StateStatus {
+    pub(crate) fn value(self) -> f64 {
+        match self {
+            Self::Idle => 0.0,
+            Self::Pending => 1.0,
+            Self::Done => 2.0,
+            Self::Failed => -1.0,
+        }
+    }
+}
+
+/// Snapshot file reads and writes, run on a worker thread so that no file
+/// I/O happens on the host's thread.
+#[derive(Debug)]
+pub(crate) struct SnapshotFiles {
+    // The job in flight: a snapshot read back, or `None` for a write.
+    job: Option<Receiver<Result<Option<Value>, String>>>,
+    pub(crate) status: StateStatus,
+}
+
+impl Default for SnapshotFiles {
+    fn default() -> Self {
+        Self {
+            job: None,
+            status: StateStatus::Idle,
+        }
+    }
+}
+
+impl SnapshotFiles {
+    pub(crate) fn write(&mut self, path: String, snapshot: Value) {
+        self.spawn(move || {
+            let text = serde_json::to_string_pretty(&snapshot).map_err(|e| e.to_string())?;
+            std::fs::write(&path, text).map_err(|e| format!("{path}: {e}"))?;
+            Ok(None)
+        });
+    }
+
+    pub(crate) fn read(&mut self, path: String) {
+        self.spawn(move || {
+            let text = std::fs::read_to_string(&path).map_err(|e| format!("{path}: {e}"))?;
+            serde_json::from_str(&text)
+                .map(Some)
+                .map_err(|e| format!("{path}: {e}"))
+        });
+    }
+
+    /// Starts `job`, dropping the result of any job still in flight.
+    fn spawn(&mut self, job: impl FnOnce() -> Result<Option<Value>, String> + Send + 'static) {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(job());
+        });
+        self.job = Some(receiver);
+        self.status = StateStatus::Pending;
+    }
+
+    /// Collects a finished job; returns the snapshot it read, if any.
+    pub(crate) fn poll(&mut self) -> Option<Value> {
+        let result = match self.job.as_ref()?.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => Err("snapshot worker stopped".into()),
+        };
+        self.job = None;
+        self.status = if result.is_ok() {
+            StateStatus::Done
+        } else {
+            StateStatus::Failed
+        };
+        result.ok().flatten()
+    }
+}
+
+impl Izhikevich2003Neuron {
+    /// Ticks of stimulus time at the start of the next tick, assuming the
+    /// host's ticks follow on from the last one.
+    fn stim_elapsed_ticks(&self) -> u64 {
+        let since_origin = match (self.stim_origin_tick, self.last_tick) {
+            (Some(origin), Some(last)) => last + 1 - origin,
+            _ => 0,
+        };
+        self.stim_offset_ticks + since_origin
+    }
+
+    pub(crate) fn snapshot(&self) -> Value {
+        // Protocols are only reachable through presets, so store the name of
+        // the first feature that carries the active one.
+        let protocol = self.protocol.and_then(|protocol| {
+            FEATURES
+                .iter()
+                .find(|preset| preset.protocol == Some(protocol))
+                .map(|preset| preset.names[0])
+        });
+        json!({
+            "kind": KIND,
+            "version": SNAPSHOT_VERSION,
+            "state": {
+                "v": self.v,
+                "u": self.u,
+                "v_mv": self.v_mv,
+                "total_spikes": self.total_spikes,
+                "last_spike_s": self.last_spike_s,
+                "time_s": self.time_s,
+            },
+            "parameters": {
+                "v0": self.v0,
+                "u0": self.u0,
+                "restart_mode": self.restart_mode.name(),
+                "a": self.a,
+                "b": self.b,
+                "c": self.c,
+                "d": self.d,
+                "modulation_modes": self.modulation.mode_names(),
+                "v_peak": self.v_peak,
+                "equations": self.equations.to_json(),
+                "g_gap": self.g_gap,
+                "input_gains": self.synapses.iter().map(|s| s.gain).collect::<Vec<_>>(),
+                "input_signs": self.synapses.iter().map(|s| s.sign).collect::<Vec<_>>(),
+                "input_spike_threshold": self.spike_threshold,
+                "spike_display": self.spike_display.name(),
+                "spike_hold_ms": self.spike_hold_ms,
+                "ap_width_ms": self.ap_width_ms,
+            },
+            "staging": self.staging.to_json(),
+            "integrator": self.stepper.to_json(),
+            "conductances": self.conductances.to_json(),
+            "output_kernel": self.output_kernel.to_json(),
+            "stimulus": {
+                "elapsed_ticks": self.stim_elapsed_ticks(),
+                "protocol": protocol,
+                "protocol_t_ms": self.protocol_t_ms,
+                "generator": self.generator.to_json(),
+            },
+            "noise": self.noise.to_json(),
+            "stdp": self.stdp.to_json(),
+            "stp": self.stp.map(Stp::to_json),
+            "delays": self.delays.to_json(),
+            "efficacy": self.synapses.map(|s| s.efficacy),
+        })
+    }
+
+    /// Restores a snapshot. Snapshots of another plugin kind or a newer
+    /// version are rejected as a whole; missing or invalid fields keep their
+    /// current values.
+    pub(crate) fn restore(&mut self, snapshot: &Value) -> Result<(), String> {
+        if snapshot.get("kind").and_then(Value::as_str) != Some(KIND) {
+            return Err("not an izhikevich_2003_neuron snapshot".into());
+        }
+        match snapshot.get("version").and_then(Value::as_u64) {
+            Some(version) if version <= SNAPSHOT_VERSION => {}
+            version => return Err(format!("unsupported snapshot version {version:?}")),
+        }
+
+        let null = Value::Null;
+        let section = |key| snapshot.get(key).unwrap_or(&null);
+
+        let state = section("state");
+        read(state, "v", &mut self.v);
+        read(state, "u", &mut self.u);
+        read(state, "v_mv", &mut self.v_mv);
+        read(state, "time_s", &mut self.time_s);
+        if let Some(total_spikes) = state.get("total_spikes").and_then(Value::as_u64) {
+            self.total_spikes = total_spikes;
+        }
+        if let Some(last_spike_s) = state.get("last_spike_s") {
+            self.last_spike_s = last_spike_s.as_f64();
+        }
+        // The host clock starts over from the next tick, whatever its value;
+        // a lower tick must not read as a host restart.
+        self.last_tick = None;
+        self.spikes_this_tick = 0;
+
+        let parameters = section("parameters");
+        read(parameters, "v0", &mut self.v0);
+        read(parameters, "u0", &mut self.u0);
+        if let Some(mode) = parameters
+            .get("restart_mode")
+            .and_then(Value::as_str)
+            .and_then(RestartMode::from_name)
+        {
+            self.restart_mode = mode;
+        }
+        read(parameters, "a", &mut self.a);
+        read(parameters, "b", &mut self.b);
+        read(parameters, "c", &mut self.c);
+        read(parameters, "d", &mut self.d);
+        read(parameters, "v_peak", &mut self.v_peak);
+        read(parameters, "g_gap", &mut self.g_gap);
+        // Snapshots from before the rename use the old key.
+        for key in ["stdp_spike_threshold", "input_spike_threshold"] {
+            read(parameters, key, &mut self.spike_threshold);
+        }
+        read(parameters, "spike_hold_ms", &mut self.spike_hold_ms);
+        read(parameters, "ap_width_ms", &mut self.ap_width_ms);
+        if let Some(equations) = parameters.get("equations") {
+            self.equations.restore(equations);
+        }
+        if let Some(modes) = parameters.get("modulation_modes").and_then(Value::as_array) {
+            for (port, mode) in modulation::INPUTS.iter().zip(modes) {
+                self.modulation.set_mode(&format!("{port}_mode"), mode);
+            }
+        }
+        for key in ["input_gains", "input_signs"] {
+            if let Some(value) = parameters.get(key) {
+                self.set_synapse_config(key, value);
+            }
+        }
+        if let Some(mode) = parameters
+            .get("spike_display")
+            .and_then(Value::as_str)
+            .and_then(SpikeDisplay::from_name)
+        {
+            self.spike_display = mode;
+        }
+
+        self.staging.restore(section("staging"));
+        self.stepper.restore(section("integrator"));
+        self.conductances.restore(section("conductances"));
+        self.output_kernel.restore(section("output_kernel"));
+
+        let stimulus = section("stimulus");
+        self.stim_origin_tick = None;
+        if let Some(elapsed) = stimulus.get("elapsed_ticks").and_then(Value::as_u64) {
+            self.stim_offset_ticks = elapsed;
+        }
+        if let Some(protocol) = stimulus.get("protocol") {
+            self.protocol = protocol
+                .as_str()
+                .and_then(presets::find)
+                .and_then(|preset| preset.protocol);
+        }
+        read(stimulus, "protocol_t_ms", &mut self.protocol_t_ms);
+        self.generator
+            .restore(stimulus.get("generator").unwrap_or(&null));
+
+        self.noise.restore(section("noise"));
+        self.stdp.restore(section("stdp"));
+        self.delays.restore(section("delays"));
+        if let Some(saved) = snapshot.get("stp").and_then(Value::as_array) {
+            for (stp, saved) in self.stp.iter_mut().zip(saved) {
+                stp.restore(saved);
+            }
+        }
+        if let Some(saved) = snapshot.get("efficacy").and_then(Value::as_array) {
+            for (slot, x) in self.synapses.iter_mut().zip(saved) {
+                slot.efficacy = x.as_f64().unwrap_or(slot.efficacy);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rtsyn_plugin::prelude::*;
+    use serde_json::Value;
+
+    use crate::Izhikevich2003Neuron;
+
+    const PERIOD_S: f64 = 1e-4;
+
+    fn driven_neuron() -> Izhikevich2003Neuron {
+        let mut neuron = Izhikevich2003Neuron::default();
+        for (key, value) in [
+            ("stim_dc", 6.0),
+            ("stim_sine_amp", 4.0),
+            ("stim_sine_freq_hz", 7.0),
+            ("noise_white_sigma", 2.0),
+        ] {
+            neuron.set_config_value(key, &Value::from(value));
+        }
+        neuron
+    }
+
+    #[test]
+    fn restored_neuron_resumes_at_any_host_tick() {
+        let (saved_at, run) = (1234, 3000);
+        let mut original = driven_neuron();
+        let mut snapshot = Value::Null;
+        for tick in 0..run {
+            original.process_tick(tick, PERIOD_S);
+            if tick == saved_at {
+                snapshot = original.snapshot();
+            }
+        }
+        assert!(original.total_spikes > 0);
+
+        for first_tick in [0, saved_at + 1, 50_000] {
+            let mut restored = Izhikevich2003Neuron::default();
+            restored.set_config_value("load_state", &snapshot);
+            for tick in saved_at + 1..run {
+                restored.process_tick(tick - saved_at - 1 + first_tick, PERIOD_S);
+            }
+            assert_eq!(restored.v.to_bits(), original.v.to_bits());
+            assert_eq!(restored.u.to_bits(), original.u.to_bits());
+            assert_eq!(restored.total_spikes, original.total_spikes);
+            assert_eq!(restored.stim_t_ms, original.stim_t_ms);
+            let since = |n: &Izhikevich2003Neuron| n.get_output_value("Time since last spike (s)");
+            assert!((since(&restored) - since(&original)).abs() < 1e-9);
+        }
+    }
+
+    fn status(neuron: &Izhikevich2003Neuron) -> Option<f64> {
+        neuron.get_internal_value("state_status")
+    }
+
+    /// Ticks until the file job in flight has finished.
+    fn wait(neuron: &mut Izhikevich2003Neuron, tick: u64) {
+        for _ in 0..1000 {
+            neuron.process_tick(tick, PERIOD_S);
+            if status(neuron) != Some(1.0) {
+                return;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        panic!("file job did not finish");
+    }
+
+    #[test]
+    fn files_are_saved_and_loaded_off_the_tick() {
+        let path =
+            std::env::temp_dir().join(format!("izhikevich_snapshot_{}.json", std::process::id()));
+        let path_value = Value::from(path.to_string_lossy().into_owned());
+        let mut original = driven_neuron();
+        (0..2000).for_each(|tick| original.process_tick(tick, PERIOD_S));
+        assert_eq!(status(&original), Some(0.0));
+        original.set_config_value("save_state", &path_value);
+        let saved_spikes = original.total_spikes;
+        wait(&mut original, 2000);
+        assert_eq!(status(&original), Some(2.0));
+
+        let mut restored = Izhikevich2003Neuron::default();
+        restored.set_config_value("load_state", &path_value);
+        // The state is only replaced at the start of a tick.
+        assert_eq!(restored.v, -65.0);
+        wait(&mut restored, 0);
+        assert_eq!(status(&restored), Some(2.0));
+        assert!(saved_spikes > 0 && restored.total_spikes >= saved_spikes);
+        std::fs::remove_file(&path).unwrap();
+
+        restored.set_config_value("load_state", &path_value);
+        wait(&mut restored, 1);
+        assert_eq!(status(&restored), Some(-1.0));
+        restored.set_config_value("load_state", &Value::from("{\"kind\": \"other\"}"));
+        assert_eq!(status(&restored), Some(-1.0));
+    }
+}
@@ -0,0 +1,201 @@
+use serde_json::{json, Value};
+
+use crate::snapshot::read;
+
+/// Parameters whose edits are staged, in the order of the value arrays below.
+pub const PARAMS: [&str; 4] = ["a", "b", "c", "d"];
+
+/// Parameter edits waiting for the next tick boundary, where they are
+/// committed together, either at once or as a linear ramp over `ramp_ms`.
+#[derive(Debug, Clone, Default)]
+pub struct Staging {
+    pending: [Option<f64>; 4],
+    pub ramp_ms: f64,
+    ramp: Option<Ramp>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Ramp {
+    from: [f64; 4],
+    to: [f64; 4],
+    elapsed_ms: f64,
+}
+
+impl Staging {
+    /// Stages `x` for `key`; returns whether `key` is one of [`PARAMS`].
+    pub fn stage(&mut self, key: &str, x: f64) -> bool {
+        let Some(k) = PARAMS.iter().position(|&p| p == key) else {
+            return false;
+        };
+        self.pending[k] = Some(x);
+        true
+    }
+
+    /// Drops pending edits and any ramp in progress.
+    pub fn clear(&mut self) {
+        self.pending = [None; 4];
+        self.ramp = None;
+    }
+
+    /// Called at a tick boundary: commits the pending edits and moves a ramp
+    /// in progress on by `period_ms`.
+    pub fn commit(&mut self, values: &mut [f64; 4], period_ms: f64) {
+        if self.pending.iter().any(Option::is_some) {
+            // Edits made mid-ramp retarget it from where it stands.
+            let mut to = self.ramp.map_or(*values, |ramp| ramp.to);
+            for (target, edit) in to.iter_mut().zip(self.pending) {
+                if let Some(x) = edit {
+                    *target = x;
+                }
+            }
+            self.pending = [None; 4];
+            self.ramp = Some(Ramp {
+                from: *values,
+                to,
+                elapsed_ms: 0.0,
+            });
+        }
+
+        let Some(ramp) = &mut self.ramp else {
+            return;
+        };
+        ramp.elapsed_ms += period_ms;
+        let frac = if self.ramp_ms > 0.0 {
+            (ramp.elapsed_ms / self.ramp_ms).min(1.0)
+        } else {
+            1.0
+        };
+        for ((value, from), to) in values.iter_mut().zip(ramp.from).zip(ramp.to) {
+            *value = from + frac * (to - from);
+        }
+        if frac >= 1.0 {
+            self.ramp = None;
+        }
+    }
+
+    /// Commits everything at once, skipping the rest of any ramp.
+    pub fn finish(&mut self, values: &mut [f64; 4]) {
+        let ramp_ms = std::mem::take(&mut self.ramp_ms);
+        self.commit(values, 0.0);
+        self.ramp_ms = ramp_ms;
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "ramp_ms": self.ramp_ms,
+            "pending": self.pending,
+            "ramp": self.ramp.map(|ramp| json!({
+                "from": ramp.from,
+                "to": ramp.to,
+                "elapsed_ms": ramp.elapsed_ms,
+            })),
+        })
+    }
+
+    pub fn restore(&mut self, value: &Value) {
+        read(value, "ramp_ms", &mut self.ramp_ms);
+        if let Some(pending) = value.get("pending").and_then(Value::as_array) {
+            for (slot, x) in self.pending.iter_mut().zip(pending) {
+                *slot = x.as_f64();
+            }
+        }
+        self.ramp = value.get("ramp").and_then(|ramp| {
+            Some(Ramp {
+                from: read_array(ramp.get("from")?)?,
+                to: read_array(ramp.get("to")?)?,
+                elapsed_ms: ramp.get("elapsed_ms")?.as_f64()?,
+            })
+        });
+    }
+}
+
+fn read_array(value: &Value) -> Option<[f64; 4]> {
+    let items = value.as_array()?;
+    let mut values = [0.0; 4];
+    if items.len() != values.len() {
+        return None;
+    }
+    for (slot, x) in values.iter_mut().zip(items) {
+        *slot = x.as_f64()?;
+    }
+    Some(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: [f64; 4] = [0.02, 0.2, -65.0, 8.0];
+
+    #[test]
+    fn edits_wait_for_the_commit_and_land_together() {
+        let mut staging = Staging::default();
+        let mut values = START;
+        assert!(staging.stage("c", -50.0) && staging.stage("d", 2.0));
+        assert!(!staging.stage("v", 0.0));
+        assert_eq!(values, START);
+        staging.commit(&mut values, 0.1);
+        assert_eq!(values, [0.02, 0.2, -50.0, 2.0]);
+        staging.commit(&mut values, 0.1);
+        assert_eq!(values, [0.02, 0.2, -50.0, 2.0]);
+    }
+
+    #[test]
+    fn ramps_are_linear_and_retarget_from_where_they_stand() {
+        let mut staging = Staging {
+            ramp_ms: 10.0,
+            ..Staging::default()
+        };
+        let mut values = START;
+        staging.stage("c", -55.0);
+        staging.commit(&mut values, 2.5);
+        assert_eq!(values[2], -62.5);
+        staging.commit(&mut values, 2.5);
+        assert_eq!(values[2], -60.0);
+
+        // A new edit starts a fresh ramp from -60 that keeps the old target.
+        staging.stage("d", 4.0);
+        staging.commit(&mut values, 5.0);
+        assert_eq!(values, [0.02, 0.2, -57.5, 6.0]);
+        staging.commit(&mut values, 10.0);
+        assert_eq!(values, [0.02, 0.2, -55.0, 4.0]);
+    }
+
+    #[test]
+    fn finish_skips_the_ramp_and_clear_drops_everything() {
+        let mut staging = Staging {
+            ramp_ms: 10.0,
+            ..Staging::default()
+        };
+        let mut values = START;
+        staging.stage("a", 0.1);
+        staging.commit(&mut values, 1.0);
+        staging.finish(&mut values);
+        assert_eq!(values[0], 0.1);
+        assert_eq!(staging.ramp_ms, 10.0);
+
+        staging.stage("b", 0.25);
+        staging.clear();
+        staging.commit(&mut values, 1.0);
+        assert_eq!(values[1], 0.2);
+    }
+
+    #[test]
+    fn snapshots_keep_pending_edits_and_ramps() {
+        let mut staging = Staging {
+            ramp_ms: 10.0,
+            ..Staging::default()
+        };
+        let mut values = START;
+        staging.stage("c", -55.0);
+        staging.commit(&mut values, 5.0);
+        staging.stage("d", 2.0);
+
+        let mut restored = Staging::default();
+        restored.restore(&staging.to_json());
+        let mut restored_values = values;
+        staging.commit(&mut values, 5.0);
+        restored.commit(&mut restored_values, 5.0);
+        assert_eq!(restored_values, values);
+    }
+}
@@ -0,0 +1,300 @@
+use std::f64::consts::TAU;
+
+use serde_json::Value;
+
+/// Piece of a stimulus protocol: `level + slope * (t - from)` for `from < t < to`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub from: f64,
+    pub to: f64,
+    pub level: f64,
+    pub slope: f64,
+}
+
+/// Injected current as a piecewise-linear function of time (ms). Outside of
+/// every segment the current is `baseline`.
+#[derive(Debug, PartialEq)]
+pub struct Protocol {
+    pub baseline: f64,
+    pub segments: &'static [Segment],
+}
+
+impl Protocol {
+    pub fn current(&self, t_ms: f64) -> f64 {
+        self.segments
+            .iter()
+            .find(|s| t_ms > s.from && t_ms < s.to)
+            .map_or(self.baseline, |s| s.level + s.slope * (t_ms - s.from))
+    }
+}
+
+pub const fn hold(from: f64, to: f64, level: f64) -> Segment {
+    Segment {
+        from,
+        to,
+        level,
+        slope: 0.0,
+    }
+}
+
+pub const fn ramp(from: f64, to: f64, level: f64, slope: f64) -> Segment {
+    Segment {
+        from,
+        to,
+        level,
+        slope,
+    }
+}
+
+/// Built-in stimulus: the sum of a DC bias, a step, a ramp, a sine, a linear
+/// chirp (ZAP) and a pulse train. A zero amplitude disables a component and a
+/// zero duration makes it last forever.
+#[derive(Debug, Clone, Copy)]
+pub struct Generator {
+    pub dc: f64,
+    pub step_amp: f64,
+    pub step_start_ms: f64,
+    pub step_duration_ms: f64,
+    /// Ramp slope in current units per second.
+    pub ramp_rate: f64,
+    pub ramp_start_ms: f64,
+    pub ramp_duration_ms: f64,
+    pub sine_amp: f64,
+    pub sine_freq_hz: f64,
+    pub chirp_amp: f64,
+    pub chirp_f0_hz: f64,
+    pub chirp_f1_hz: f64,
+    pub chirp_duration_ms: f64,
+    pub pulse_amp: f64,
+    pub pulse_width_ms: f64,
+    pub pulse_freq_hz: f64,
+    pub pulse_start_ms: f64,
+    pub pulse_count: u64,
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Self {
+            dc: 0.0,
+            step_amp: 0.0,
+            step_start_ms: 0.0,
+            step_duration_ms: 0.0,
+            ramp_rate: 0.0,
+            ramp_start_ms: 0.0,
+            ramp_duration_ms: 0.0,
+            sine_amp: 0.0,
+            sine_freq_hz: 10.0,
+            chirp_amp: 0.0,
+            chirp_f0_hz: 0.0,
+            chirp_f1_hz: 20.0,
+            chirp_duration_ms: 10_000.0,
+            pulse_amp: 0.0,
+            pulse_width_ms: 1.0,
+            pulse_freq_hz: 10.0,
+            pulse_start_ms: 0.0,
+            pulse_count: 0,
+        }
+    }
+}
+
+fn within(t_ms: f64, start_ms: f64, duration_ms: f64) -> bool {
+    t_ms >= start_ms && (duration_ms <= 0.0 || t_ms < start_ms + duration_ms)
+}
+
+impl Generator {
+    pub fn current(&self, t_ms: f64) -> f64 {
+        let mut i = self.dc;
+
+        if self.step_amp != 0.0 && within(t_ms, self.step_start_ms, self.step_duration_ms) {
+            i += self.step_amp;
+        }
+
+        if self.ramp_rate != 0.0 && within(t_ms, self.ramp_start_ms, self.ramp_duration_ms) {
+            i += self.ramp_rate * (t_ms - self.ramp_start_ms) / 1000.0;
+        }
+
+        if self.sine_amp != 0.0 {
+            i += self.sine_amp * (TAU * self.sine_freq_hz * t_ms / 1000.0).sin();
+        }
+
+        if self.chirp_amp != 0.0 && self.chirp_duration_ms > 0.0 && t_ms < self.chirp_duration_ms {
+            let t = t_ms / 1000.0;
+            let sweep = (self.chirp_f1_hz - self.chirp_f0_hz) / (self.chirp_duration_ms / 1000.0);
+            i += self.chirp_amp * (TAU * (self.chirp_f0_hz * t + 0.5 * sweep * t * t)).sin();
+        }
+
+        if self.pulse_amp != 0.0 && self.pulse_freq_hz > 0.0 && t_ms >= self.pulse_start_ms {
+            let period_ms = 1000.0 / self.pulse_freq_hz;
+            let since_ms = t_ms - self.pulse_start_ms;
+            let index = (since_ms / period_ms).floor();
+            let in_train = self.pulse_count == 0 || index < self.pulse_count as f64;
+            if in_train && since_ms - index * period_ms < self.pulse_width_ms {
+                i += self.pulse_amp;
+            }
+        }
+
+        i
+    }
+
+    /// Sets a `stim_*` config key; returns whether `key` was one of them.
+    pub fn set(&mut self, key: &str, x: f64) -> bool {
+        let Some(key) = key.strip_prefix("stim_") else {
+            return false;
+        };
+        let field = match key {
+            "dc" => &mut self.dc,
+            "step_amp" => &mut self.step_amp,
+            "step_start_ms" => &mut self.step_start_ms,
+            "step_duration_ms" => &mut self.step_duration_ms,
+            "ramp_rate" => &mut self.ramp_rate,
+            "ramp_start_ms" => &mut self.ramp_start_ms,
+            "ramp_duration_ms" => &mut self.ramp_duration_ms,
+            "sine_amp" => &mut self.sine_amp,
+            "sine_freq_hz" => &mut self.sine_freq_hz,
+            "chirp_amp" => &mut self.chirp_amp,
+            "chirp_f0_hz" => &mut self.chirp_f0_hz,
+            "chirp_f1_hz" => &mut self.chirp_f1_hz,
+            "chirp_duration_ms" => &mut self.chirp_duration_ms,
+            "pulse_amp" => &mut self.pulse_amp,
+            "pulse_width_ms" => &mut self.pulse_width_ms,
+            "pulse_freq_hz" => &mut self.pulse_freq_hz,
+            "pulse_start_ms" => &mut self.pulse_start_ms,
+            "pulse_count" => {
+                self.pulse_count = x.max(0.0) as u64;
+                return true;
+            }
+            _ => return false,
+        };
+        *field = x;
+        true
+    }
+
+    /// The `stim_*` config keys and their current values.
+    pub fn values(&self) -> Vec<(&'static str, Value)> {
+        vec![
+            ("stim_dc", self.dc.into()),
+            ("stim_step_amp", self.step_amp.into()),
+            ("stim_step_start_ms", self.step_start_ms.into()),
+            ("stim_step_duration_ms", self.step_duration_ms.into()),
+            ("stim_ramp_rate", self.ramp_rate.into()),
+            ("stim_ramp_start_ms", self.ramp_start_ms.into()),
+            ("stim_ramp_duration_ms", self.ramp_duration_ms.into()),
+            ("stim_sine_amp", self.sine_amp.into()),
+            ("stim_sine_freq_hz", self.sine_freq_hz.into()),
+            ("stim_chirp_amp", self.chirp_amp.into()),
+            ("stim_chirp_f0_hz", self.chirp_f0_hz.into()),
+            ("stim_chirp_f1_hz", self.chirp_f1_hz.into()),
+            ("stim_chirp_duration_ms", self.chirp_duration_ms.into()),
+            ("stim_pulse_amp", self.pulse_amp.into()),
+            ("stim_pulse_width_ms", self.pulse_width_ms.into()),
+            ("stim_pulse_freq_hz", self.pulse_freq_hz.into()),
+            ("stim_pulse_start_ms", self.pulse_start_ms.into()),
+            ("stim_pulse_count", self.pulse_count.into()),
+        ]
+    }
+
+    pub fn default_vars() -> Vec<(&'static str, Value)> {
+        Self::default().values()
+    }
+
+    pub fn to_json(&self) -> Value {
+        self.values()
+            .into_iter()
+            .map(|(key, x)| (key.to_string(), x))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+
+    pub fn restore(&mut self, value: &Value) {
+        for (key, _) in self.values() {
+            if let Some(x) = value.get(key).and_then(Value::as_f64) {
+                self.set(key, x);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn protocols_are_piecewise_linear() {
+        const PROTOCOL: Protocol = Protocol {
+            baseline: 1.0,
+            segments: &[hold(10.0, 20.0, 5.0), ramp(30.0, 40.0, 0.0, 2.0)],
+        };
+        let protocol = &PROTOCOL;
+        assert_eq!(protocol.current(5.0), 1.0);
+        assert_eq!(protocol.current(15.0), 5.0);
+        assert_eq!(protocol.current(25.0), 1.0);
+        assert_eq!(protocol.current(35.0), 10.0);
+    }
+
+    #[test]
+    fn components_switch_on_and_off() {
+        let mut generator = Generator::default();
+        for (key, x) in [
+            ("stim_dc", 1.0),
+            ("stim_step_amp", 2.0),
+            ("stim_step_start_ms", 100.0),
+            ("stim_step_duration_ms", 50.0),
+            ("stim_ramp_rate", 1000.0),
+            ("stim_ramp_start_ms", 200.0),
+        ] {
+            assert!(generator.set(key, x));
+        }
+        assert!(!generator.set("dc", 1.0));
+        assert_eq!(generator.current(50.0), 1.0);
+        assert_eq!(generator.current(120.0), 3.0);
+        assert_eq!(generator.current(150.0), 1.0);
+        // A zero duration lasts forever.
+        assert_eq!(generator.current(1200.0), 1.0 + 1000.0);
+    }
+
+    #[test]
+    fn pulse_trains_stop_after_their_count() {
+        let generator = Generator {
+            pulse_amp: 1.0,
+            pulse_width_ms: 2.0,
+            pulse_freq_hz: 100.0,
+            pulse_start_ms: 5.0,
+            pulse_count: 3,
+            ..Generator::default()
+        };
+        let pulses: Vec<f64> = (0..60).map(|t| generator.current(t as f64)).collect();
+        let onsets: Vec<usize> = (1..60).filter(|&t| pulses[t] > pulses[t - 1]).collect();
+        assert_eq!(onsets, [5, 15, 25]);
+        assert_eq!(pulses.iter().sum::<f64>(), 6.0);
+    }
+
+    #[test]
+    fn sine_and_chirp_oscillate_at_their_frequencies() {
+        let sine = Generator {
+            sine_amp: 1.0,
+            sine_freq_hz: 10.0,
+            ..Generator::default()
+        };
+        assert!((sine.current(25.0) - 1.0).abs() < 1e-12);
+        assert!(sine.current(50.0).abs() < 1e-12);
+
+        // The chirp's instantaneous frequency sweeps from f0 to f1.
+        let chirp = Generator {
+            chirp_amp: 1.0,
+            chirp_f0_hz: 0.0,
+            chirp_f1_hz: 20.0,
+            chirp_duration_ms: 1000.0,
+            ..Generator::default()
+        };
+        let crossings = |from: usize, to: usize| {
+            (from..to)
+                .filter(|&t| {
+                    let (a, b) = (chirp.current(t as f64), chirp.current(t as f64 + 1.0));
+                    a < 0.0 && b >= 0.0
+                })
+                .count()
+        };
+        assert!(crossings(0, 500) < crossings(500, 1000));
+        assert_eq!(chirp.current(1500.0), 0.0);
+    }
+}
@@ -0,0 +1,201 @@
+use serde_json::{json, Value};
+
+use crate::snapshot::read;
+use crate::synapse::MAX_INPUTS;
+
+/// Tsodyks-Markram short-term plasticity of one input. A presynaptic spike
+/// raises the utilisation `u` by `U (1 - u)`, releases `u x` of the resources
+/// `x`, and sets the efficacy to `u x / U`, so the first spike from rest has
+/// efficacy 1. Between spikes `x` recovers to 1 with `tau_rec_ms` and `u`
+/// decays to 0 with `tau_facil_ms`; with `tau_facil_ms = 0`, `u` stays at
+/// `U` and the synapse only depresses.
+#[derive(Debug, Clone, Copy)]
+pub struct Stp {
+    pub u_base: f64,
+    pub tau_rec_ms: f64,
+    pub tau_facil_ms: f64,
+    u: f64,
+    x: f64,
+    efficacy: f64,
+}
+
+impl Default for Stp {
+    // `U = 1` with instant recovery leaves every efficacy at 1.
+    fn default() -> Self {
+        Self {
+            u_base: 1.0,
+            tau_rec_ms: 0.0,
+            tau_facil_ms: 0.0,
+            u: 0.0,
+            x: 1.0,
+            efficacy: 1.0,
+        }
+    }
+}
+
+impl Stp {
+    /// Efficacy of the last presynaptic spike, held until the next one.
+    pub fn efficacy(&self) -> f64 {
+        self.efficacy
+    }
+
+    /// Handles a presynaptic spike at the start of a tick, then lets the
+    /// resources recover over the tick.
+    pub fn tick(&mut self, spiked: bool, period_ms: f64) {
+        if spiked {
+            self.u = if self.tau_facil_ms > 0.0 {
+                self.u + self.u_base * (1.0 - self.u)
+            } else {
+                self.u_base
+            };
+            let released = self.u * self.x;
+            self.efficacy = if self.u_base > 0.0 {
+                released / self.u_base
+            } else {
+                0.0
+            };
+            self.x -= released;
+        }
+
+        self.x = if self.tau_rec_ms > 0.0 {
+            1.0 - (1.0 - self.x) * (-period_ms / self.tau_rec_ms).exp()
+        } else {
+            1.0
+        };
+        if self.tau_facil_ms > 0.0 {
+            self.u *= (-period_ms / self.tau_facil_ms).exp();
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.u = 0.0;
+        self.x = 1.0;
+        self.efficacy = 1.0;
+    }
+
+    fn set(&mut self, field: &str, x: f64) {
+        match field {
+            "stp_u" if (0.0..=1.0).contains(&x) => self.u_base = x,
+            "stp_tau_rec_ms" if x >= 0.0 => self.tau_rec_ms = x,
+            "stp_tau_facil_ms" if x >= 0.0 => self.tau_facil_ms = x,
+            _ => {}
+        }
+    }
+
+    pub fn to_json(self) -> Value {
+        json!({
+            "u_base": self.u_base,
+            "tau_rec_ms": self.tau_rec_ms,
+            "tau_facil_ms": self.tau_facil_ms,
+            "u": self.u,
+            "x": self.x,
+            "efficacy": self.efficacy,
+        })
+    }
+
+    pub fn restore(&mut self, value: &Value) {
+        read(value, "u_base", &mut self.u_base);
+        read(value, "tau_rec_ms", &mut self.tau_rec_ms);
+        read(value, "tau_facil_ms", &mut self.tau_facil_ms);
+        read(value, "u", &mut self.u);
+        read(value, "x", &mut self.x);
+        read(value, "efficacy", &mut self.efficacy);
+    }
+}
+
+/// Handles the `stp_u`, `stp_tau_rec_ms` and `stp_tau_facil_ms` keys: an
+/// array sets the inputs in order, a number sets all of them. Returns whether
+/// `key` was one of them.
+pub fn set_config(stp: &mut [Stp; MAX_INPUTS], key: &str, value: &Value) -> bool {
+    if !matches!(key, "stp_u" | "stp_tau_rec_ms" | "stp_tau_facil_ms") {
+        return false;
+    }
+    if let Some(items) = value.as_array() {
+        for (slot, item) in stp.iter_mut().zip(items) {
+            if let Some(x) = item.as_f64() {
+                slot.set(key, x);
+            }
+        }
+    } else if let Some(x) = value.as_f64() {
+        stp.iter_mut().for_each(|slot| slot.set(key, x));
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Efficacies of a regular train of `n` spikes, `isi_ms` apart.
+    fn train(stp: &mut Stp, n: usize, isi_ms: f64) -> Vec<f64> {
+        (0..n)
+            .map(|_| {
+                stp.tick(true, isi_ms);
+                stp.efficacy()
+            })
+            .collect()
+    }
+
+    fn synapse(u_base: f64, tau_rec_ms: f64, tau_facil_ms: f64) -> Stp {
+        Stp {
+            u_base,
+            tau_rec_ms,
+            tau_facil_ms,
+            ..Stp::default()
+        }
+    }
+
+    #[test]
+    fn defaults_are_static() {
+        assert!(train(&mut Stp::default(), 5, 10.0)
+            .iter()
+            .all(|&e| e == 1.0));
+    }
+
+    #[test]
+    fn depressing_synapses_follow_tsodyks_markram() {
+        let (u, tau_rec, isi) = (0.5, 100.0, 20.0);
+        let efficacies = train(&mut synapse(u, tau_rec, 0.0), 20, isi);
+        assert_eq!(efficacies[0], 1.0);
+        // Resources left after a spike recover towards 1 until the next one.
+        let x1 = 1.0 - u;
+        let x2 = 1.0 - (1.0 - x1) * (-isi / tau_rec).exp();
+        assert!((efficacies[1] - x2).abs() < 1e-12);
+        assert!(efficacies.windows(2).all(|w| w[1] <= w[0]));
+        // The steady state of the recursion x = 1 - (1 - x (1 - U)) e^(-isi / tau).
+        let decay = (-isi / tau_rec).exp();
+        let steady = (1.0 - decay) / (1.0 - (1.0 - u) * decay);
+        assert!((efficacies[19] - steady).abs() < 1e-6);
+        // A long pause brings it back.
+        let mut pause = synapse(u, tau_rec, 0.0);
+        train(&mut pause, 20, isi);
+        pause.tick(false, 2000.0);
+        assert!(train(&mut pause, 1, isi)[0] > 0.99);
+    }
+
+    #[test]
+    fn facilitating_synapses_grow_and_reset_returns_to_rest() {
+        let mut facilitating = synapse(0.1, 50.0, 500.0);
+        let efficacies = train(&mut facilitating, 5, 10.0);
+        assert_eq!(efficacies[0], 1.0);
+        assert!(efficacies[1] > 1.5 && efficacies[2] > efficacies[1]);
+        facilitating.reset();
+        assert_eq!(train(&mut facilitating, 1, 10.0), [1.0]);
+    }
+
+    #[test]
+    fn config_sets_all_inputs_or_each_in_order() {
+        let mut stp = [Stp::default(); MAX_INPUTS];
+        assert!(set_config(&mut stp, "stp_u", &Value::from(0.3)));
+        assert!(set_config(
+            &mut stp,
+            "stp_tau_rec_ms",
+            &serde_json::json!([100.0, -1.0])
+        ));
+        assert!(!set_config(&mut stp, "stp_x", &Value::from(0.3)));
+        assert!(stp.iter().all(|s| s.u_base == 0.3));
+        assert_eq!((stp[0].tau_rec_ms, stp[1].tau_rec_ms), (100.0, 0.0));
+        set_config(&mut stp, "stp_u", &Value::from(1.5));
+        assert_eq!(stp[0].u_base, 0.3);
+    }
+}
@@ -0,0 +1,100 @@
+/// Upper bound on user-added inputs; storage is fixed so the real-time path
+/// never allocates.
+pub const MAX_INPUTS: usize = 8;
+
+/// Port name prefix of the extendable inputs: `in_0`, `in_1`, ...
+pub const INPUT_PREFIX: &str = "in_";
+
+/// One user-added synaptic input and its weighting.
+#[derive(Debug, Clone, Copy)]
+pub struct Synapse {
+    pub value: f64,
+    pub gain: f64,
+    /// `1.0` for excitatory, `-1.0` for inhibitory inputs.
+    pub sign: f64,
+    /// Short-term plasticity factor, 1 for static synapses.
+    pub efficacy: f64,
+}
+
+impl Default for Synapse {
+    fn default() -> Self {
+        Self {
+            value: 0.0,
+            gain: 1.0,
+            sign: 1.0,
+            efficacy: 1.0,
+        }
+    }
+}
+
+impl Synapse {
+    pub fn current(&self) -> f64 {
+        self.sign * self.gain * self.efficacy * self.value
+    }
+}
+
+/// Index of an extendable input port such as `in_3`.
+pub fn input_index(key: &str) -> Option<usize> {
+    key.strip_prefix(INPUT_PREFIX)?
+        .parse()
+        .ok()
+        .filter(|&k| k < MAX_INPUTS)
+}
+
+/// Index of an `in_<k>` port past [`MAX_INPUTS`], whose values are dropped.
+pub fn ignored_input_index(key: &str) -> Option<usize> {
+    key.strip_prefix(INPUT_PREFIX)?
+        .parse()
+        .ok()
+        .filter(|&k| k >= MAX_INPUTS)
+}
+
+/// Parses `excitatory`/`inhibitory` (or a signed number) into `1.0`/`-1.0`.
+pub fn parse_sign(value: &serde_json::Value) -> Option<f64> {
+    if let Some(x) = value.as_f64() {
+        return (x != 0.0).then(|| x.signum());
+    }
+    match value.as_str()?.trim().to_ascii_lowercase().as_str() {
+        "excitatory" | "exc" | "e" | "+" => Some(1.0),
+        "inhibitory" | "inh" | "i" | "-" => Some(-1.0),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn ports_past_the_cap_are_ignored_not_dropped_silently() {
+        assert_eq!(input_index("in_0"), Some(0));
+        assert_eq!(input_index("in_7"), Some(MAX_INPUTS - 1));
+        assert_eq!(input_index("in_8"), None);
+        assert_eq!(ignored_input_index("in_8"), Some(MAX_INPUTS));
+        assert_eq!(ignored_input_index("in_7"), None);
+        assert_eq!(input_index("i_syn"), None);
+        assert_eq!(ignored_input_index("in_x"), None);
+    }
+
+    #[test]
+    fn signs_are_parsed_from_names_or_numbers() {
+        assert_eq!(parse_sign(&json!("Inhibitory")), Some(-1.0));
+        assert_eq!(parse_sign(&json!(" exc ")), Some(1.0));
+        assert_eq!(parse_sign(&json!(-0.5)), Some(-1.0));
+        assert_eq!(parse_sign(&json!(0.0)), None);
+        assert_eq!(parse_sign(&json!("shunting")), None);
+    }
+
+    #[test]
+    fn current_is_signed_and_scaled() {
+        let synapse = Synapse {
+            value: 2.0,
+            gain: 3.0,
+            sign: -1.0,
+            efficacy: 0.5,
+        };
+        assert_eq!(synapse.current(), -3.0);
+    }
+}